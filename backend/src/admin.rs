@@ -0,0 +1,343 @@
+use crate::{
+    auth,
+    config::Config,
+    error::Error,
+    game::{ConnId, Rooms},
+};
+use actix_web::{delete, get, http::header, post, web, HttpRequest, HttpResponse, Scope};
+use futures_util::future::join_all;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+pub struct Notice {
+    pub message: String,
+}
+
+/// Every endpoint of the admin API, under `/admin/api`.
+pub fn scope() -> Scope {
+    web::scope("/admin/api")
+        .service(list_rooms)
+        .service(show_room)
+        .service(current_round)
+        .service(kick)
+        .service(close_room)
+        .service(notice)
+}
+
+fn error(response: &mut actix_web::HttpResponseBuilder, message: &str) -> HttpResponse {
+    response.json(json!({ "error": message }))
+}
+
+/// Whether the request carries the admin token, when one is configured.
+pub fn is_admin(req: &HttpRequest, config: &Config) -> bool {
+    let Some(token) = &config.admin_token else {
+        return false;
+    };
+
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| auth::same_secret(token, given.trim()))
+}
+
+/// Turns the request down unless it carries the admin token. The API looks
+/// like it isn't there when no token is configured.
+fn reject(req: &HttpRequest, config: &Config) -> Option<HttpResponse> {
+    if config.admin_token.is_none() {
+        return Some(HttpResponse::NotFound().body("404 - Not Found"));
+    }
+    if is_admin(req, config) {
+        return None;
+    }
+
+    log::warn!("Unauthorized admin API request: {}", req.path());
+    Some(error(
+        HttpResponse::Unauthorized().insert_header((header::WWW_AUTHENTICATE, "Bearer")),
+        "Missing or invalid admin token",
+    ))
+}
+
+fn room_not_found() -> HttpResponse {
+    error(&mut HttpResponse::NotFound(), "Room not found")
+}
+
+fn failed(err: Error) -> HttpResponse {
+    match err {
+        Error::UserNotFound(_) => error(&mut HttpResponse::NotFound(), "User not found"),
+        err => {
+            log::error!("Admin API request failed: {}", err);
+            error(&mut HttpResponse::InternalServerError(), &err.to_string())
+        }
+    }
+}
+
+#[get("/rooms")]
+pub async fn list_rooms(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    let running = rooms.running();
+    let infos = join_all(running.iter().map(|(_, handle)| handle.inspect())).await;
+
+    // rooms closing in the meantime are left out
+    let mut infos = infos.into_iter().flatten().collect::<Vec<_>>();
+    infos.sort_by(|a, b| a.room.cmp(&b.room));
+
+    HttpResponse::Ok().json(infos)
+}
+
+#[get("/rooms/{room}")]
+pub async fn show_room(
+    req: HttpRequest,
+    room: web::Path<String>,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    let Some(handle) = rooms.get(&room) else {
+        return room_not_found();
+    };
+
+    match handle.inspect().await {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(err) => failed(err),
+    }
+}
+
+#[get("/rooms/{room}/round")]
+pub async fn current_round(
+    req: HttpRequest,
+    room: web::Path<String>,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    let Some(handle) = rooms.get(&room) else {
+        return room_not_found();
+    };
+
+    match handle.current_round().await {
+        Ok(round) => HttpResponse::Ok().json(round),
+        Err(err) => failed(err),
+    }
+}
+
+#[delete("/rooms/{room}/users/{conn_id}")]
+pub async fn kick(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    let (room, conn_id) = path.into_inner();
+    let Ok(conn_id) = conn_id.parse::<ConnId>() else {
+        return error(&mut HttpResponse::BadRequest(), "Invalid connection id");
+    };
+
+    let Some(handle) = rooms.get(&room) else {
+        return room_not_found();
+    };
+
+    match handle.remove(&conn_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => failed(err),
+    }
+}
+
+#[delete("/rooms/{room}")]
+pub async fn close_room(
+    req: HttpRequest,
+    room: web::Path<String>,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    match rooms.close(&room).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => room_not_found(),
+        Err(err) => failed(err),
+    }
+}
+
+/// Shows a message to everyone, in every room.
+#[post("/notice")]
+pub async fn notice(
+    req: HttpRequest,
+    body: web::Json<Notice>,
+    config: web::Data<Config>,
+    rooms: web::Data<Rooms>,
+) -> HttpResponse {
+    if let Some(response) = reject(&req, &config) {
+        return response;
+    }
+
+    let message = body.message.trim();
+    if message.is_empty() {
+        return error(&mut HttpResponse::BadRequest(), "The notice can't be empty");
+    }
+
+    let running = rooms.running();
+    let results = join_all(running.iter().map(|(_, handle)| handle.notice(message))).await;
+    let delivered = results.iter().filter(|result| result.is_ok()).count();
+
+    HttpResponse::Ok().json(json!({ "rooms": delivered }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Credentials;
+    use actix_web::{http::StatusCode, test, App};
+    use shared::OutboundMessage;
+    use tokio::sync::mpsc;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn get(uri: &str) -> test::TestRequest {
+        test::TestRequest::get()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", TOKEN)))
+    }
+
+    #[actix_web::test]
+    async fn test_authorization() {
+        let rooms = Rooms::new(Default::default());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config {
+                    admin_token: Some(TOKEN.to_string()),
+                    ..Config::default()
+                }))
+                .app_data(web::Data::new(rooms))
+                .service(scope()),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/api/rooms")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/api/rooms")
+            .insert_header((header::AUTHORIZATION, "Bearer 0123456789abcdeF"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = test::call_service(&app, get("/admin/api/rooms").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // no token, no API
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config::default()))
+                .app_data(web::Data::new(Rooms::new(Default::default())))
+                .service(scope()),
+        )
+        .await;
+        let res = test::call_service(&app, get("/admin/api/rooms").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_manage_rooms() {
+        let rooms = Rooms::new(Default::default());
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx_1, mut rx_1) = mpsc::unbounded_channel();
+        let (tx_2, mut rx_2) = mpsc::unbounded_channel();
+        handle
+            .connect(tx_1, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        let conn_id_2 = handle
+            .connect(tx_2, "Player2", false, &Credentials::default())
+            .await
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Config {
+                    admin_token: Some(TOKEN.to_string()),
+                    ..Config::default()
+                }))
+                .app_data(web::Data::new(rooms.clone()))
+                .service(scope()),
+        )
+        .await;
+
+        let res: serde_json::Value =
+            test::call_and_read_body_json(&app, get("/admin/api/rooms").to_request()).await;
+        assert_eq!(res[0]["room"], "team-a");
+        assert_eq!(res[0]["users"][0]["nickname"], "Player1");
+        assert_eq!(res[0]["users"][0]["facilitator"], true);
+        assert_eq!(res[0]["users"][1]["conn_id"], conn_id_2.to_string());
+
+        let res = test::call_service(&app, get("/admin/api/rooms/team-b").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res: serde_json::Value =
+            test::call_and_read_body_json(&app, get("/admin/api/rooms/team-a/round").to_request())
+                .await;
+        assert_eq!(res["revealed"], false);
+        assert_eq!(res["votes"][0][0], "Player1");
+        assert!(res.get("results").is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/admin/api/notice")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", TOKEN)))
+            .set_json(json!({ "message": "Server restart at noon" }))
+            .to_request();
+        let res: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res["rooms"], 1);
+        let expected = OutboundMessage::Notice("Server restart at noon".to_string());
+        assert!(std::iter::from_fn(|| rx_2.try_recv().ok()).any(|message| message == expected));
+
+        let uri = format!("/admin/api/rooms/team-a/users/{}", conn_id_2);
+        let req = get(&uri)
+            .method(actix_web::http::Method::DELETE)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let req = get(&uri)
+            .method(actix_web::http::Method::DELETE)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let req = get("/admin/api/rooms/team-a/users/nobody")
+            .method(actix_web::http::Method::DELETE)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = get("/admin/api/rooms/team-a")
+            .method(actix_web::http::Method::DELETE)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(std::iter::from_fn(|| rx_1.try_recv().ok())
+            .any(|message| message == OutboundMessage::RoomClosed));
+        assert!(rooms.get("team-a").is_none());
+    }
+}
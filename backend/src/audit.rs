@@ -0,0 +1,110 @@
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    Join,
+    Resume,
+    Leave,
+    Rename,
+    Vote,
+    Reveal,
+    Claim,
+    Promote,
+    Kick,
+    ForceAway,
+    ClearVotes,
+    Lock,
+    Unlock,
+    SetPassword,
+    RemovePassword,
+    Close,
+    Notice,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    time: String,
+    room: &'a str,
+    event: Event,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nickname: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+}
+
+/// Append-only record of what happened in the rooms, one JSON object per line.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// `nickname` is who did it, if anyone, and `detail` what it was done to
+    /// or with, like the target of a kick or the card of a vote.
+    pub fn record(&self, room: &str, event: Event, nickname: Option<&str>, detail: Option<&str>) {
+        let entry = Entry {
+            time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            room,
+            event,
+            nickname,
+            detail,
+        };
+        let line = serde_json::to_string(&entry).expect("failed to serialize audit entry");
+
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if let Err(err) = writeln!(file, "{}", line) {
+            log::error!("Failed to write the audit log: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_record() {
+        let path = std::env::temp_dir().join(format!("omnipoker-{}.log", uuid::Uuid::new_v4()));
+        fs::write(&path, "{}\n").unwrap();
+
+        let audit = AuditLog::open(&path).unwrap();
+        audit.record("team-a", Event::Join, Some("Alice"), None);
+        audit.record("team-a", Event::Kick, Some("Alice"), Some("Bob"));
+        audit.record("team-a", Event::Reveal, None, None);
+
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        fs::remove_file(path).unwrap();
+
+        // entries are appended to what is already there
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1]["room"], "team-a");
+        assert_eq!(lines[1]["event"], "join");
+        assert_eq!(lines[1]["nickname"], "Alice");
+        assert!(lines[1].get("detail").is_none());
+        assert_eq!(lines[2]["event"], "kick");
+        assert_eq!(lines[2]["detail"], "Bob");
+        assert!(lines[3].get("nickname").is_none());
+        assert!(lines[3]["time"].as_str().unwrap().ends_with('Z'));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    error::{Error, Result},
+    game::Credentials,
+};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::{sync::Arc, time::Duration};
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(key: &[u8], message: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(message.as_bytes());
+    mac
+}
+
+fn random_bytes() -> [u8; 32] {
+    let mut bytes = [0; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Salts and hashes a room password with Argon2id, as a PHC string such as
+/// `$argon2id$v=19$m=19456,t=2,p=1$salt$hash` which records its parameters.
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("the default Argon2 parameters are valid")
+        .to_string()
+}
+
+/// Also accepts the `salt$hash` HMAC of rooms stored by older versions.
+pub fn check_password(hashed: &str, password: &str) -> bool {
+    if let Ok(hash) = PasswordHash::new(hashed) {
+        return Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok();
+    }
+
+    let Some((salt, hash)) = hashed.split_once('$') else {
+        return false;
+    };
+
+    match (hex::decode(salt), hex::decode(hash)) {
+        (Ok(salt), Ok(hash)) => mac(&salt, password).verify_slice(&hash).is_ok(),
+        _ => false,
+    }
+}
+
+/// Lets anyone into an open room; a protected one, whose password hashed
+/// to `hashed`, wants that password or a valid invite.
+pub fn authenticate(
+    room_id: &str,
+    hashed: Option<&str>,
+    invites: &Invites,
+    credentials: &Credentials,
+) -> Result<()> {
+    let Some(hashed) = hashed else {
+        return Ok(());
+    };
+
+    if let Some(invite) = &credentials.invite {
+        return invites.verify(room_id, invite);
+    }
+
+    match &credentials.password {
+        Some(password) if check_password(hashed, password.trim()) => Ok(()),
+        Some(_) => Err(Error::WrongPassword),
+        None => Err(Error::PasswordRequired),
+    }
+}
+
+/// Compares secrets in constant time, so the time taken doesn't tell how
+/// much of a guess was right.
+pub fn same_secret(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Signs invites to a room, as `expiry.signature` where the expiry is a unix
+/// timestamp and the signature covers both the room and the expiry.
+#[derive(Clone)]
+pub struct Invites {
+    secret: Arc<[u8]>,
+}
+
+impl std::fmt::Debug for Invites {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Invites")
+    }
+}
+
+impl Invites {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    /// Invites signed with a secret of their own won't outlive the process.
+    pub fn random() -> Self {
+        Self::new(&random_bytes())
+    }
+
+    pub fn create(&self, room_id: &str, lifetime: Duration) -> String {
+        let expiry = chrono::Utc::now().timestamp() + lifetime.as_secs() as i64;
+        let signature = mac(&self.secret, &format!("{}:{}", room_id, expiry))
+            .finalize()
+            .into_bytes();
+
+        format!("{}.{}", expiry, hex::encode(signature))
+    }
+
+    pub fn verify(&self, room_id: &str, invite: &str) -> Result<()> {
+        let (expiry, signature) = invite.split_once('.').ok_or(Error::InvalidInvite)?;
+        let signature = hex::decode(signature).map_err(|_| Error::InvalidInvite)?;
+
+        mac(&self.secret, &format!("{}:{}", room_id, expiry))
+            .verify_slice(&signature)
+            .map_err(|_| Error::InvalidInvite)?;
+
+        match expiry.parse::<i64>() {
+            Ok(expiry) if expiry > chrono::Utc::now().timestamp() => Ok(()),
+            _ => Err(Error::InvalidInvite),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password() {
+        let hashed = hash_password("correct horse");
+
+        assert!(check_password(&hashed, "correct horse"));
+        assert!(!check_password(&hashed, "wrong horse"));
+        assert_ne!(hashed, hash_password("correct horse"));
+        assert!(!check_password("garbage", "correct horse"));
+        assert!(hashed.starts_with("$argon2id$"));
+
+        let legacy_salt = [7; 32];
+        let legacy = format!(
+            "{}${}",
+            hex::encode(legacy_salt),
+            hex::encode(mac(&legacy_salt, "correct horse").finalize().into_bytes())
+        );
+        assert!(check_password(&legacy, "correct horse"));
+        assert!(!check_password(&legacy, "wrong horse"));
+    }
+
+    #[test]
+    fn test_same_secret() {
+        assert!(same_secret("0123456789abcdef", "0123456789abcdef"));
+        assert!(!same_secret("0123456789abcdef", "0123456789abcdeF"));
+        assert!(!same_secret("0123456789abcdef", "0123456789"));
+        assert!(!same_secret("0123456789abcdef", ""));
+    }
+
+    #[test]
+    fn test_invites() {
+        let invites = Invites::new(b"secret");
+        let invite = invites.create("team-a", Duration::from_secs(60));
+
+        assert!(invites.verify("team-a", &invite).is_ok());
+        assert!(matches!(
+            invites.verify("team-b", &invite),
+            Err(Error::InvalidInvite)
+        ));
+        assert!(matches!(
+            Invites::new(b"other").verify("team-a", &invite),
+            Err(Error::InvalidInvite)
+        ));
+
+        // moving the expiry breaks the signature
+        let (_, signature) = invite.split_once('.').unwrap();
+        let forged = format!("{}.{}", i64::MAX, signature);
+        assert!(invites.verify("team-a", &forged).is_err());
+
+        let expired = invites.create("team-a", Duration::ZERO);
+        assert!(invites.verify("team-a", &expired).is_err());
+        assert!(invites.verify("team-a", "not an invite").is_err());
+    }
+}
@@ -1,23 +1,94 @@
-use crate::{limit::Limit, logger::LogLevel};
+use crate::{
+    config::Config,
+    error::Result,
+    logger::{LogFormat, LogLevel},
+};
 use clap::Parser;
+use std::{env, path::PathBuf};
 
 #[derive(clap::Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
     #[arg(help = "Specify the address for the server (e.g., '127.0.0.1:8080').")]
     addr: Option<String>,
+    #[arg(short, long, help = "Read the settings from this TOML file.")]
+    config: Option<PathBuf>,
     #[arg(short, long, help = "Specify the maximum limit of users.")]
-    limit: Option<Limit>,
+    limit: Option<usize>,
+    #[arg(long, help = "Specify the maximum limit of users per room.")]
+    room_limit: Option<usize>,
     #[arg(long, help = "Log level")]
     log: Option<LogLevel>,
+    #[arg(long, help = "Log as colored text or one JSON object per line.")]
+    log_format: Option<LogFormat>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Append the joins, votes and moderation actions to this file."
+    )]
+    audit_log: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Keep rooms, stories and history in this directory across restarts."
+    )]
+    data_dir: Option<PathBuf>,
+    #[arg(long, help = "Serve over TLS with this PEM certificate chain.")]
+    tls_cert: Option<PathBuf>,
+    #[arg(long, help = "Private key (PEM) of the TLS certificate.")]
+    tls_key: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "ADDR",
+        help = "Redirect plain HTTP requests received on this address to HTTPS."
+    )]
+    http_redirect: Option<String>,
 }
 
-pub fn get_args() -> (String, Limit, LogLevel) {
+pub fn get_config() -> Result<Config> {
     let cli = Cli::parse();
 
-    let addr = cli.addr.as_deref().unwrap_or("127.0.0.1:8080");
-    let limit = cli.limit.unwrap_or_default();
-    let log_level = cli.log.unwrap_or_default();
+    let path = cli
+        .config
+        .or_else(|| env::var_os("OMNIPOKER_CONFIG").map(PathBuf::from));
+    let mut config = match path {
+        Some(path) => Config::from_file(&path)?,
+        None => Config::default(),
+    };
 
-    (addr.to_owned(), limit, log_level)
+    config.apply_env(|name| env::var(name).ok())?;
+
+    if let Some(addr) = cli.addr {
+        config.addr = addr;
+    }
+    if let Some(limit) = cli.limit {
+        config.limit = limit;
+    }
+    if let Some(room_limit) = cli.room_limit {
+        config.room_limit = room_limit;
+    }
+    if let Some(log) = cli.log {
+        config.log = log;
+    }
+    if let Some(log_format) = cli.log_format {
+        config.log_format = log_format;
+    }
+    if let Some(audit_log) = cli.audit_log {
+        config.audit_log = Some(audit_log);
+    }
+    if let Some(data_dir) = cli.data_dir {
+        config.data_dir = Some(data_dir);
+    }
+    if let Some(tls_cert) = cli.tls_cert {
+        config.tls_cert = Some(tls_cert);
+    }
+    if let Some(tls_key) = cli.tls_key {
+        config.tls_key = Some(tls_key);
+    }
+    if let Some(http_redirect) = cli.http_redirect {
+        config.http_redirect = Some(http_redirect);
+    }
+
+    config.validate()?;
+
+    Ok(config)
 }
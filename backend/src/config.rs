@@ -0,0 +1,425 @@
+use crate::{
+    auth::Invites,
+    error::{Error, Result},
+    game::{Deck, RoomSettings},
+    logger::{LogFormat, LogLevel},
+};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{fs, path::Path, path::PathBuf, str::FromStr, time::Duration};
+
+const ENV_PREFIX: &str = "OMNIPOKER_";
+const MAX_NICKNAME_LEN: usize = 100;
+
+/// Server settings, read from the config file, then `OMNIPOKER_*` variables,
+/// then the command line, each one overriding the previous.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub addr: String,
+    pub limit: usize,
+    pub room_limit: usize,
+    pub log: LogLevel,
+    pub log_format: LogFormat,
+    /// Appends the joins, votes and moderation actions of every room to this file.
+    pub audit_log: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    /// Seconds between the pings sent to each client.
+    pub heartbeat_interval: u64,
+    /// Seconds without an answer before a client is considered gone.
+    pub client_timeout: u64,
+    /// Seconds a dropped user keeps their seat for `/resume`.
+    pub resume_grace: u64,
+    pub max_nickname_len: usize,
+    pub max_frame_size: usize,
+    pub max_message_size: usize,
+    /// A preset name or a comma separated list of cards.
+    pub default_deck: String,
+    /// Seconds given to the clients to leave when the server shuts down.
+    pub drain_timeout: u64,
+    /// Sent to the clients along with the shutdown notice, e.g. "back in 5 minutes".
+    pub shutdown_message: Option<String>,
+    /// PEM certificate chain and private key to serve over TLS.
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Address of a plain HTTP listener redirecting to the TLS one.
+    pub http_redirect: Option<String>,
+    /// Signs the invite links; without it they don't survive a restart.
+    pub invite_secret: Option<String>,
+    /// Bearer token of the admin API, which is off without it.
+    pub admin_token: Option<String>,
+    /// Sessions allowed from the same address at the same time.
+    pub max_connections_per_ip: usize,
+    /// Takes the client address from the `Forwarded` or `X-Forwarded-For`
+    /// header, for servers only reachable through a reverse proxy.
+    pub trust_proxy: bool,
+    /// Messages a client may send every second, on average.
+    pub message_rate: u32,
+    /// Messages a client may send in a row before `message_rate` applies.
+    pub message_burst: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:8080".to_string(),
+            limit: 15,
+            room_limit: 15,
+            log: LogLevel::default(),
+            log_format: LogFormat::default(),
+            audit_log: None,
+            data_dir: None,
+            heartbeat_interval: 5,
+            client_timeout: 10,
+            resume_grace: 30,
+            max_nickname_len: 20,
+            max_frame_size: 128 * 1024,
+            max_message_size: 2 * 1024 * 1024,
+            default_deck: "fibonacci".to_string(),
+            drain_timeout: 10,
+            shutdown_message: None,
+            tls_cert: None,
+            tls_key: None,
+            http_redirect: None,
+            invite_secret: None,
+            admin_token: None,
+            max_connections_per_ip: 3,
+            trust_proxy: false,
+            message_rate: 5,
+            message_burst: 20,
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value.trim().parse().map_err(|_| {
+        Error::InvalidConfig(format!("{}{}: invalid value '{}'", ENV_PREFIX, name, value))
+    })
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::InvalidConfig(format!("can't read {}: {}", path.display(), err))
+        })?;
+
+        toml::from_str(&content)
+            .map_err(|err| Error::InvalidConfig(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Overrides the settings with the `OMNIPOKER_*` variables found by `var`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let get = |name: &str| var(&format!("{}{}", ENV_PREFIX, name));
+
+        if let Some(value) = get("ADDR") {
+            self.addr = value;
+        }
+        if let Some(value) = get("LIMIT") {
+            self.limit = parse_env("LIMIT", &value)?;
+        }
+        if let Some(value) = get("ROOM_LIMIT") {
+            self.room_limit = parse_env("ROOM_LIMIT", &value)?;
+        }
+        if let Some(value) = get("LOG") {
+            self.log = LogLevel::from_str(value.trim(), true).map_err(|_| {
+                Error::InvalidConfig(format!("{}LOG: invalid value '{}'", ENV_PREFIX, value))
+            })?;
+        }
+        if let Some(value) = get("LOG_FORMAT") {
+            self.log_format = LogFormat::from_str(value.trim(), true).map_err(|_| {
+                Error::InvalidConfig(format!(
+                    "{}LOG_FORMAT: invalid value '{}'",
+                    ENV_PREFIX, value
+                ))
+            })?;
+        }
+        if let Some(value) = get("AUDIT_LOG") {
+            self.audit_log = Some(PathBuf::from(value));
+        }
+        if let Some(value) = get("DATA_DIR") {
+            self.data_dir = Some(PathBuf::from(value));
+        }
+        if let Some(value) = get("HEARTBEAT_INTERVAL") {
+            self.heartbeat_interval = parse_env("HEARTBEAT_INTERVAL", &value)?;
+        }
+        if let Some(value) = get("CLIENT_TIMEOUT") {
+            self.client_timeout = parse_env("CLIENT_TIMEOUT", &value)?;
+        }
+        if let Some(value) = get("RESUME_GRACE") {
+            self.resume_grace = parse_env("RESUME_GRACE", &value)?;
+        }
+        if let Some(value) = get("MAX_NICKNAME_LEN") {
+            self.max_nickname_len = parse_env("MAX_NICKNAME_LEN", &value)?;
+        }
+        if let Some(value) = get("MAX_FRAME_SIZE") {
+            self.max_frame_size = parse_env("MAX_FRAME_SIZE", &value)?;
+        }
+        if let Some(value) = get("MAX_MESSAGE_SIZE") {
+            self.max_message_size = parse_env("MAX_MESSAGE_SIZE", &value)?;
+        }
+        if let Some(value) = get("DEFAULT_DECK") {
+            self.default_deck = value;
+        }
+        if let Some(value) = get("DRAIN_TIMEOUT") {
+            self.drain_timeout = parse_env("DRAIN_TIMEOUT", &value)?;
+        }
+        if let Some(value) = get("SHUTDOWN_MESSAGE") {
+            self.shutdown_message = Some(value);
+        }
+        if let Some(value) = get("TLS_CERT") {
+            self.tls_cert = Some(PathBuf::from(value));
+        }
+        if let Some(value) = get("TLS_KEY") {
+            self.tls_key = Some(PathBuf::from(value));
+        }
+        if let Some(value) = get("HTTP_REDIRECT") {
+            self.http_redirect = Some(value);
+        }
+        if let Some(value) = get("INVITE_SECRET") {
+            self.invite_secret = Some(value);
+        }
+        if let Some(value) = get("ADMIN_TOKEN") {
+            self.admin_token = Some(value);
+        }
+        if let Some(value) = get("MAX_CONNECTIONS_PER_IP") {
+            self.max_connections_per_ip = parse_env("MAX_CONNECTIONS_PER_IP", &value)?;
+        }
+        if let Some(value) = get("TRUST_PROXY") {
+            self.trust_proxy = parse_env("TRUST_PROXY", &value)?;
+        }
+        if let Some(value) = get("MESSAGE_RATE") {
+            self.message_rate = parse_env("MESSAGE_RATE", &value)?;
+        }
+        if let Some(value) = get("MESSAGE_BURST") {
+            self.message_burst = parse_env("MESSAGE_BURST", &value)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| Err(Error::InvalidConfig(reason));
+
+        if self.addr.trim().is_empty() {
+            return invalid("addr can't be empty".to_string());
+        }
+        if self.limit == 0 || self.room_limit == 0 {
+            return invalid("limit and room_limit must be at least 1".to_string());
+        }
+        if self.max_connections_per_ip == 0 {
+            return invalid("max_connections_per_ip must be at least 1".to_string());
+        }
+        if self.message_rate == 0 || self.message_burst == 0 {
+            return invalid("message_rate and message_burst must be at least 1".to_string());
+        }
+        if self.heartbeat_interval == 0 {
+            return invalid("heartbeat_interval must be at least 1 second".to_string());
+        }
+        if self.client_timeout <= self.heartbeat_interval {
+            return invalid(format!(
+                "client_timeout ({}s) must be longer than heartbeat_interval ({}s)",
+                self.client_timeout, self.heartbeat_interval
+            ));
+        }
+        if self.max_nickname_len == 0 || self.max_nickname_len > MAX_NICKNAME_LEN {
+            return invalid(format!(
+                "max_nickname_len must be between 1 and {}",
+                MAX_NICKNAME_LEN
+            ));
+        }
+        if self.max_frame_size == 0 {
+            return invalid("max_frame_size must be at least 1 byte".to_string());
+        }
+        if self.max_message_size < self.max_frame_size {
+            return invalid(format!(
+                "max_message_size ({}) can't be smaller than max_frame_size ({})",
+                self.max_message_size, self.max_frame_size
+            ));
+        }
+        if let Err(reason) = self.default_deck.parse::<Deck>() {
+            return invalid(format!("default_deck: {}", reason));
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            return invalid("tls_cert and tls_key must be set together".to_string());
+        }
+        if self
+            .invite_secret
+            .as_ref()
+            .is_some_and(|secret| secret.len() < 16)
+        {
+            return invalid("invite_secret must be at least 16 characters long".to_string());
+        }
+        if self
+            .admin_token
+            .as_ref()
+            .is_some_and(|token| token.len() < 16)
+        {
+            return invalid("admin_token must be at least 16 characters long".to_string());
+        }
+        if self.http_redirect.is_some() && !self.tls() {
+            return invalid("http_redirect needs tls_cert and tls_key".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn tls(&self) -> bool {
+        self.tls_cert.is_some() && self.tls_key.is_some()
+    }
+
+    /// The port the server listens on, 443 if the address doesn't say.
+    pub fn port(&self) -> u16 {
+        self.addr
+            .rsplit_once(':')
+            .and_then(|(_, port)| port.parse().ok())
+            .unwrap_or(443)
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval)
+    }
+
+    pub fn client_timeout(&self) -> Duration {
+        Duration::from_secs(self.client_timeout)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout)
+    }
+
+    pub fn room_settings(&self) -> RoomSettings {
+        RoomSettings {
+            max_nickname_len: self.max_nickname_len,
+            default_deck: self
+                .default_deck
+                .parse()
+                .expect("the config is validated at startup"),
+            resume_grace: Duration::from_secs(self.resume_grace),
+            invites: match &self.invite_secret {
+                Some(secret) => Invites::new(secret.as_bytes()),
+                None => Invites::random(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+            addr = "0.0.0.0:80"
+            log = "debug"
+            client_timeout = 20
+            default_deck = "t-shirt"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.addr, "0.0.0.0:80");
+        assert!(matches!(config.log, LogLevel::Debug));
+        assert_eq!(config.client_timeout(), Duration::from_secs(20));
+        assert_eq!(config.heartbeat_interval, 5);
+        assert_eq!(config.room_settings().default_deck, Deck::t_shirt());
+
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let vars = HashMap::from([
+            ("OMNIPOKER_LIMIT", "30"),
+            ("OMNIPOKER_LOG", "warn"),
+            ("OMNIPOKER_DATA_DIR", "/var/lib/omnipoker"),
+            ("OMNIPOKER_SHUTDOWN_MESSAGE", "back in 5 minutes"),
+            ("OMNIPOKER_MESSAGE_RATE", "2"),
+            ("OMNIPOKER_LOG_FORMAT", "json"),
+            ("OMNIPOKER_TRUST_PROXY", "true"),
+        ]);
+        let var = |name: &str| vars.get(name).map(|value| value.to_string());
+
+        let mut config = Config::default();
+        config.apply_env(var).unwrap();
+        assert_eq!(config.limit, 30);
+        assert!(matches!(config.log, LogLevel::Warn));
+        assert_eq!(config.data_dir, Some(PathBuf::from("/var/lib/omnipoker")));
+        assert_eq!(
+            config.shutdown_message.as_deref(),
+            Some("back in 5 minutes")
+        );
+        assert_eq!(config.message_rate, 2);
+        assert!(matches!(config.log_format, LogFormat::Json));
+        assert!(config.trust_proxy);
+
+        let err = config
+            .apply_env(|name| (name == "OMNIPOKER_ROOM_LIMIT").then(|| "many".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: OMNIPOKER_ROOM_LIMIT: invalid value 'many'"
+        );
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = Config {
+            client_timeout: 5,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid configuration: client_timeout (5s) must be longer than heartbeat_interval (5s)"
+        );
+
+        let config = Config {
+            default_deck: "cards".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid configuration: default_deck: Unknown deck: cards"
+        );
+
+        let config = Config {
+            tls_cert: Some(PathBuf::from("cert.pem")),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid configuration: tls_cert and tls_key must be set together"
+        );
+
+        let config = Config {
+            admin_token: Some("secret".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid configuration: admin_token must be at least 16 characters long"
+        );
+
+        let config = Config {
+            message_burst: 0,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid configuration: message_rate and message_burst must be at least 1"
+        );
+
+        let config = Config {
+            http_redirect: Some("0.0.0.0:80".to_string()),
+            ..Config::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::game::Command;
 use crate::game::ConnId;
-use shared::OutboundMessage;
+use shared::{ErrorCode, OutboundMessage, PROTOCOL_VERSION};
 use tokio::sync::{mpsc::error::SendError, oneshot::error::RecvError};
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -9,21 +9,52 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     NicknameCannotBeEmpty,
     NicknameAlreadyInUse(String),
+    InvalidRoomName(String),
+    RoomIsFull,
+    RoomIsLocked,
+    PasswordRequired,
+    WrongPassword,
+    InvalidInvite,
+    InvalidInviteLifetime(u64),
+    NotFacilitator,
+    InvalidFacilitatorToken,
+    NicknameNotFound(String),
+    InvalidDeck(String),
+    RoundAlreadyRevealed,
+    RoundNotRevealed,
+    InvalidStory(String),
+    StoryNotFound(String),
+    NoCurrentStory,
+    InvalidEstimate,
+    ObserverCannotVote,
+    InvalidVote(String),
+    InvalidCommand(String),
+    NotJoined,
+    AlreadyJoined,
+    InvalidTimer(u64),
+    NoTimerRunning,
+    InvalidResumeToken,
+    ShuttingDown,
+    UnsupportedProtocol(u32),
+    TooManyConnections,
+    TooManyMessages,
+    Storage(String),
+    InvalidConfig(String),
     UserNotFound(ConnId),
-    SendMessage(SendError<OutboundMessage>),
-    SendCommand(SendError<Command>),
+    SendMessage(Box<SendError<OutboundMessage>>),
+    SendCommand(Box<SendError<Command>>),
     Recv(RecvError),
 }
 
 impl From<SendError<Command>> for Error {
     fn from(err: SendError<Command>) -> Self {
-        Error::SendCommand(err)
+        Error::SendCommand(Box::new(err))
     }
 }
 
 impl From<SendError<OutboundMessage>> for Error {
     fn from(err: SendError<OutboundMessage>) -> Self {
-        Error::SendMessage(err)
+        Error::SendMessage(Box::new(err))
     }
 }
 
@@ -33,6 +64,48 @@ impl From<RecvError> for Error {
     }
 }
 
+impl Error {
+    /// What JSON clients get in a `Nack`.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::InvalidCommand(_) => ErrorCode::InvalidCommand,
+            Error::NotJoined => ErrorCode::NotJoined,
+            Error::AlreadyJoined => ErrorCode::AlreadyJoined,
+            Error::NicknameCannotBeEmpty => ErrorCode::InvalidNickname,
+            Error::NicknameAlreadyInUse(_) => ErrorCode::NicknameInUse,
+            Error::InvalidRoomName(_) => ErrorCode::InvalidRoom,
+            Error::RoomIsFull => ErrorCode::RoomFull,
+            Error::RoomIsLocked => ErrorCode::RoomLocked,
+            Error::PasswordRequired => ErrorCode::PasswordRequired,
+            Error::WrongPassword => ErrorCode::WrongPassword,
+            Error::InvalidInvite | Error::InvalidInviteLifetime(_) => ErrorCode::InvalidInvite,
+            Error::NotFacilitator => ErrorCode::NotFacilitator,
+            Error::InvalidFacilitatorToken | Error::InvalidResumeToken => ErrorCode::InvalidToken,
+            Error::NicknameNotFound(_) => ErrorCode::UserNotFound,
+            Error::InvalidDeck(_) => ErrorCode::InvalidDeck,
+            Error::InvalidVote(_) => ErrorCode::InvalidVote,
+            Error::ObserverCannotVote => ErrorCode::ObserverCannotVote,
+            Error::RoundAlreadyRevealed => ErrorCode::RoundRevealed,
+            Error::RoundNotRevealed => ErrorCode::RoundNotRevealed,
+            Error::InvalidStory(_) => ErrorCode::InvalidStory,
+            Error::StoryNotFound(_) => ErrorCode::StoryNotFound,
+            Error::NoCurrentStory => ErrorCode::NoCurrentStory,
+            Error::InvalidEstimate => ErrorCode::InvalidEstimate,
+            Error::InvalidTimer(_) => ErrorCode::InvalidTimer,
+            Error::NoTimerRunning => ErrorCode::NoTimerRunning,
+            Error::UnsupportedProtocol(_) => ErrorCode::UnsupportedProtocol,
+            Error::TooManyConnections | Error::TooManyMessages => ErrorCode::RateLimited,
+            Error::ShuttingDown => ErrorCode::ShuttingDown,
+            Error::Storage(_)
+            | Error::InvalidConfig(_)
+            | Error::UserNotFound(_)
+            | Error::SendMessage(_)
+            | Error::SendCommand(_)
+            | Error::Recv(_) => ErrorCode::Internal,
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -40,6 +113,55 @@ impl std::fmt::Display for Error {
             Error::NicknameAlreadyInUse(nickname) => {
                 write!(f, "Nickname {} is already in use", nickname)
             }
+            Error::InvalidRoomName(room) => write!(f, "Invalid room name: {}", room),
+            Error::RoomIsFull => write!(f, "Room is full"),
+            Error::RoomIsLocked => write!(f, "Room is locked"),
+            Error::PasswordRequired => write!(f, "This room needs a password"),
+            Error::WrongPassword => write!(f, "Wrong password"),
+            Error::InvalidInvite => write!(f, "This invite is invalid or has expired"),
+            Error::InvalidInviteLifetime(max) => {
+                write!(f, "An invite must last between 1 and {} minutes", max)
+            }
+            Error::NotFacilitator => write!(f, "Only the facilitator can do that"),
+            Error::InvalidFacilitatorToken => write!(f, "Invalid facilitator token"),
+            Error::NicknameNotFound(nickname) => write!(f, "There is no user named {}", nickname),
+            Error::InvalidDeck(reason) => write!(f, "Invalid deck: {}", reason),
+            Error::RoundAlreadyRevealed => {
+                write!(f, "Votes are already revealed; start the next round first")
+            }
+            Error::RoundNotRevealed => {
+                write!(f, "Reveal the votes before accepting an estimate")
+            }
+            Error::InvalidStory(reason) => write!(f, "Invalid story: {}", reason),
+            Error::StoryNotFound(key) => write!(f, "Story {} not found", key),
+            Error::NoCurrentStory => write!(f, "There is no story being estimated"),
+            Error::InvalidEstimate => write!(f, "An estimate can't be empty"),
+            Error::ObserverCannotVote => {
+                write!(f, "Observers can't vote; use /setback to join the voting")
+            }
+            Error::InvalidVote(vote) => write!(f, "{} is not a card of this deck", vote),
+            Error::InvalidCommand(reason) => write!(f, "{}", reason),
+            Error::NotJoined => write!(f, "Join a room first with /join <nickname>"),
+            Error::AlreadyJoined => {
+                write!(f, "You are already in a room; reconnect to join another")
+            }
+            Error::InvalidTimer(max) => {
+                write!(f, "A timer must last between 1 and {} seconds", max)
+            }
+            Error::NoTimerRunning => write!(f, "There is no timer running"),
+            Error::ShuttingDown => write!(f, "The server is shutting down"),
+            Error::UnsupportedProtocol(version) => write!(
+                f,
+                "Protocol version {} is not supported; this server speaks version {}",
+                version, PROTOCOL_VERSION
+            ),
+            Error::TooManyConnections => write!(f, "Too many connections from your address"),
+            Error::TooManyMessages => write!(f, "Too many messages, slow down"),
+            Error::InvalidResumeToken => {
+                write!(f, "This session can't be resumed anymore; join again")
+            }
+            Error::Storage(reason) => write!(f, "Storage error: {}", reason),
+            Error::InvalidConfig(reason) => write!(f, "Invalid configuration: {}", reason),
             Error::UserNotFound(conn_id) => write!(f, "User with id {} not found", conn_id),
             Error::SendMessage(err) => write!(f, "Failed to send message: {}", err),
             Error::SendCommand(err) => write!(f, "Failed to send command: {}", err),
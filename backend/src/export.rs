@@ -0,0 +1,109 @@
+use shared::RoundRecord;
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(records: &[RoundRecord]) -> String {
+    let mut csv = String::from("round,timestamp,story,votes,estimate\n");
+
+    for (i, record) in records.iter().enumerate() {
+        let estimate = record
+            .estimate
+            .as_ref()
+            .map(|estimate| estimate.to_string())
+            .unwrap_or_default();
+
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            i + 1,
+            record.timestamp,
+            csv_field(&record.story_label()),
+            csv_field(&record.votes_label()),
+            csv_field(&estimate),
+        ));
+    }
+
+    csv
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+pub fn to_markdown(room: &str, records: &[RoundRecord]) -> String {
+    let mut markdown = format!(
+        "# Planning poker: {}\n\n| # | Time | Story | Votes | Estimate |\n| - | ---- | ----- | ----- | -------- |\n",
+        room
+    );
+
+    for (i, record) in records.iter().enumerate() {
+        let estimate = record
+            .estimate
+            .as_ref()
+            .map(|estimate| estimate.to_string())
+            .unwrap_or_default();
+
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            i + 1,
+            record.timestamp,
+            markdown_cell(&record.story_label()),
+            markdown_cell(&record.votes_label()),
+            markdown_cell(&estimate),
+        ));
+    }
+
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{Story, Vote};
+
+    fn records() -> Vec<RoundRecord> {
+        vec![
+            RoundRecord {
+                timestamp: "2024-11-20T10:00:00Z".into(),
+                story: Some(Story::new("PROJ-1", "Login, \"remember me\"")),
+                votes: vec![
+                    ("Player1".into(), Vote::from(3)),
+                    ("Player2".into(), Vote::from(5)),
+                ],
+                estimate: Some(Vote::from(5)),
+            },
+            RoundRecord {
+                timestamp: "2024-11-20T10:05:00Z".into(),
+                story: None,
+                votes: vec![("Player1".into(), Vote::Unknown)],
+                estimate: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            to_csv(&records()),
+            "round,timestamp,story,votes,estimate\n\
+             1,2024-11-20T10:00:00Z,\"PROJ-1 Login, \"\"remember me\"\"\",\"Player1: 3, Player2: 5\",5\n\
+             2,2024-11-20T10:05:00Z,,Player1: ?,\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = to_markdown("team-a", &records());
+
+        assert!(markdown.starts_with("# Planning poker: team-a\n"));
+        assert!(markdown.contains(
+            "| 1 | 2024-11-20T10:00:00Z | PROJ-1 Login, \"remember me\" | Player1: 3, Player2: 5 | 5 |\n"
+        ));
+        assert!(markdown.ends_with("| 2 | 2024-11-20T10:05:00Z |  | Player1: ? |  |\n"));
+    }
+}
@@ -1,5 +1,8 @@
+#[allow(clippy::module_inception)]
 mod game;
 mod game_handle;
+mod rooms;
 
 pub use game::*;
 pub use game_handle::*;
+pub use rooms::*;
@@ -1,14 +1,33 @@
-use super::game_handle::*;
-use crate::error::{Error, Result};
+use super::{game_handle::*, RoomId};
+use crate::{
+    audit::{AuditLog, Event},
+    auth::{self, Invites},
+    error::{Error, Result},
+    limit::Limit,
+    metrics::Metrics,
+    storage::{RoomState, Storage},
+};
+use serde::Serialize;
 use shared::VoteStatus;
-pub use shared::{OutboundMessage, UserStatus, Vote};
-use std::{cmp::Ordering, collections::HashMap};
-use tokio::sync::mpsc;
+pub use shared::{
+    Deck, OutboundMessage, Participant, RoundRecord, Story, UserStatus, Vote, VoteStats,
+};
+use std::{cmp::Ordering, collections::HashMap, str::FromStr, sync::Arc};
+use tokio::{
+    sync::mpsc,
+    task::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use uuid::Uuid;
 
 pub type Nickname = String;
 
+const MAX_HISTORY: usize = 500;
+const MAX_TIMER_SECONDS: u64 = 3600;
+const TIMER_TICK: Duration = Duration::from_secs(1);
+const MAX_INVITE_MINUTES: u64 = 7 * 24 * 60;
+
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub struct ConnId(Uuid);
 
@@ -18,6 +37,14 @@ impl ConnId {
     }
 }
 
+impl FromStr for ConnId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(ConnId)
+    }
+}
+
 impl std::fmt::Display for ConnId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -31,6 +58,10 @@ pub struct User {
     vote: Vote,
     status: UserStatus,
     ord: usize,
+    joined: usize,
+    token: String,
+    /// Set while the connection is gone, until the user resumes or the grace period ends.
+    suspended: Option<Instant>,
 }
 
 impl User {
@@ -41,7 +72,20 @@ impl User {
 
 type UsersMap = HashMap<ConnId, User>;
 
-fn validate_nickname<'a>(nickname: &'a str, users: &UsersMap) -> Result<&'a str> {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Round {
+    Voting,
+    Revealed,
+}
+
+#[derive(Debug)]
+struct Timer {
+    remaining: u64,
+    next_tick: Instant,
+    reveal: bool,
+}
+
+fn validate_nickname<'a>(nickname: &'a str, users: &UsersMap, max_len: usize) -> Result<&'a str> {
     let nickname = nickname.trim();
 
     if nickname.is_empty() {
@@ -49,29 +93,118 @@ fn validate_nickname<'a>(nickname: &'a str, users: &UsersMap) -> Result<&'a str>
         return Err(Error::NicknameCannotBeEmpty);
     }
 
+    let nickname = match nickname.char_indices().nth(max_len) {
+        Some((end, _)) => {
+            log::warn!("Nickname too long, truncating: {}", nickname);
+            nickname[..end].trim_end()
+        }
+        None => nickname,
+    };
+
     if users.values().any(|user| user.nickname == nickname) {
         log::error!("Nickname already in use: {}", nickname);
         return Err(Error::NicknameAlreadyInUse(nickname.into()));
     }
 
-    let nickname = if nickname.len() > 20 {
-        log::warn!("Nickname too long, truncating: {}", nickname);
-        &nickname[..20]
-    } else {
-        nickname
-    };
-
     Ok(nickname)
 }
 
+/// Knobs of every room, taken from the server configuration.
+#[derive(Clone, Debug)]
+pub struct RoomSettings {
+    pub max_nickname_len: usize,
+    pub default_deck: Deck,
+    pub resume_grace: Duration,
+    pub invites: Invites,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            max_nickname_len: 20,
+            default_deck: Deck::default(),
+            resume_grace: Duration::from_secs(30),
+            invites: Invites::random(),
+        }
+    }
+}
+
+/// A user as the admin API shows it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UserInfo {
+    pub conn_id: String,
+    pub nickname: Nickname,
+    pub status: UserStatus,
+    pub facilitator: bool,
+    pub voted: bool,
+    pub suspended: bool,
+}
+
+/// A room as the admin API shows it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RoomInfo {
+    pub room: RoomId,
+    pub deck: Deck,
+    pub locked: bool,
+    pub protected: bool,
+    pub current_story: Option<String>,
+    pub users: Vec<UserInfo>,
+}
+
+/// The votes of the current round, which stay hidden until they are revealed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RoundInfo {
+    pub revealed: bool,
+    pub story: Option<String>,
+    pub votes: Vec<(Nickname, VoteStatus)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Vec<(Nickname, Vote)>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<VoteStats>,
+}
+
+/// What a user shows to get into a password protected room.
+#[derive(Clone, Debug, Default)]
+pub struct Credentials {
+    pub password: Option<String>,
+    pub invite: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct GameServer {
     pub users: UsersMap,
-    pub cmd_rx: mpsc::UnboundedReceiver<Command>,
+    pub cmd_rx: mpsc::UnboundedReceiver<Queued>,
+    room_id: RoomId,
+    limit: Limit,
+    deck: Deck,
+    round: Round,
+    auto_reveal: bool,
+    facilitator: Option<ConnId>,
+    facilitator_token: String,
+    locked: bool,
+    password: Option<String>,
+    joins: usize,
+    stories: Vec<Story>,
+    current_story: Option<String>,
+    history: Vec<RoundRecord>,
+    timer: Option<Timer>,
+    weak_cmd_tx: mpsc::WeakUnboundedSender<Queued>,
+    keep_alive: Option<mpsc::UnboundedSender<Queued>>,
+    storage: Option<(RoomId, Arc<dyn Storage>)>,
+    /// Set when something worth storing changed since the last save.
+    dirty: bool,
+    saving: Option<JoinHandle<()>>,
+    settings: RoomSettings,
+    metrics: Option<(RoomId, Arc<Metrics>)>,
+    audit: Option<Arc<AuditLog>>,
+}
+
+fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
 }
 
 impl GameServer {
-    pub fn new() -> (Self, GameHandle) {
+    pub fn new(room_id: &str, limit: Limit, settings: RoomSettings) -> (Self, GameHandle) {
         log::info!("Game started");
 
         let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
@@ -80,303 +213,2182 @@ impl GameServer {
             Self {
                 users: UsersMap::new(),
                 cmd_rx,
+                room_id: room_id.to_string(),
+                limit,
+                deck: settings.default_deck.clone(),
+                round: Round::Voting,
+                auto_reveal: true,
+                facilitator: None,
+                facilitator_token: new_token(),
+                locked: false,
+                password: None,
+                joins: 0,
+                stories: Vec::new(),
+                current_story: None,
+                history: Vec::new(),
+                timer: None,
+                weak_cmd_tx: cmd_tx.downgrade(),
+                keep_alive: None,
+                storage: None,
+                dirty: false,
+                saving: None,
+                settings,
+                metrics: None,
+                audit: None,
             },
             GameHandle { cmd_tx },
         )
     }
 
+    /// Restores the room from a state loaded from the storage, and keeps it
+    /// there from now on.
+    pub fn attach_storage(
+        &mut self,
+        room_id: &str,
+        storage: Arc<dyn Storage>,
+        state: Option<RoomState>,
+    ) {
+        if let Some(state) = state {
+            log::debug!("Room restored: {}", room_id);
+
+            self.deck = state.deck;
+            self.auto_reveal = state.auto_reveal;
+            self.stories = state.stories;
+            self.current_story = state.current_story;
+            self.history = state.history;
+            self.password = state.password;
+        }
+
+        self.storage = Some((room_id.to_string(), storage));
+    }
+
+    pub fn attach_metrics(&mut self, room_id: &str, metrics: Arc<Metrics>) {
+        self.metrics = Some((room_id.to_string(), metrics));
+    }
+
+    pub fn attach_audit(&mut self, audit: Arc<AuditLog>) {
+        self.audit = Some(audit);
+    }
+
+    fn audit(&self, event: Event, by: Option<&ConnId>, detail: Option<&str>) {
+        if let Some(audit) = &self.audit {
+            let nickname = by
+                .and_then(|id| self.users.get(id))
+                .map(|user| user.nickname.as_str());
+            audit.record(&self.room_id, event, nickname, detail);
+        }
+    }
+
+    /// Updates the number of users in the room, leaving the suspended ones out.
+    pub fn report_users(&self) {
+        if let Some((room_id, metrics)) = &self.metrics {
+            let users = self
+                .users
+                .values()
+                .filter(|user| user.suspended.is_none())
+                .count();
+            metrics.set_room_users(room_id, users);
+        }
+    }
+
+    pub fn report_queued(&self, at: Instant) {
+        if let Some((_, metrics)) = &self.metrics {
+            metrics.command_queued(at.elapsed());
+        }
+    }
+
+    pub fn report_finished(&self) {
+        if let Some((room_id, metrics)) = &self.metrics {
+            metrics.remove_room(room_id);
+        }
+    }
+
+    fn room_state(&self) -> RoomState {
+        RoomState {
+            deck: self.deck.clone(),
+            auto_reveal: self.auto_reveal,
+            stories: self.stories.clone(),
+            current_story: self.current_story.clone(),
+            history: self.history.clone(),
+            password: self.password.clone(),
+        }
+    }
+
+    /// Writes the room to the storage if anything worth keeping changed,
+    /// off the room's task but after the writes before it.
+    pub fn persist(&mut self) {
+        let Some((room_id, storage)) = &self.storage else {
+            return;
+        };
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        let (room_id, storage, state) = (room_id.clone(), storage.clone(), self.room_state());
+        let previous = self.saving.take();
+        self.saving = Some(tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+
+            let saved = task::spawn_blocking(move || {
+                storage
+                    .save(&room_id, &state)
+                    .map_err(|err| format!("Failed to save room {}: {}", room_id, err))
+            })
+            .await;
+            match saved {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => log::error!("{}", err),
+                Err(err) => log::error!("Failed to save room: {}", err),
+            }
+        }));
+    }
+
+    /// Waits for the writes still in progress.
+    pub async fn flush(&mut self) {
+        if let Some(saving) = self.saving.take() {
+            let _ = saving.await;
+        }
+    }
+
     pub async fn connect(
         &mut self,
         tx: mpsc::UnboundedSender<OutboundMessage>,
         nickname: &str,
+        observer: bool,
+        credentials: &Credentials,
     ) -> Result<ConnId> {
-        log::info!("User identified: {}", nickname);
+        log::info!(
+            room = self.room_id.as_str(),
+            nickname,
+            event = "identify";
+            "User identified: {}", nickname
+        );
+
+        self.authenticate(credentials)?;
 
-        let nickname = validate_nickname(nickname, &self.users)?;
+        if self.limit.is_reached() {
+            log::warn!("Room is full; rejecting {}", nickname);
+            return Err(Error::RoomIsFull);
+        }
+
+        if self.locked {
+            log::warn!("Room is locked; rejecting {}", nickname);
+            return Err(Error::RoomIsLocked);
+        }
+
+        let nickname = validate_nickname(nickname, &self.users, self.settings.max_nickname_len)?;
+
+        let status = if observer {
+            UserStatus::Observer
+        } else {
+            UserStatus::Active
+        };
 
         // register session with random connection ID
         let user = User {
             nickname: nickname.to_string(),
             tx,
             vote: Vote::Null,
-            status: UserStatus::Active,
+            status: status.clone(),
             ord: 0,
+            joined: self.joins,
+            token: new_token(),
+            suspended: None,
         };
 
         let conn_id = ConnId::new();
+        let token = user.token.clone();
 
+        self.joins += 1;
         self.users.insert(conn_id.clone(), user);
+        self.limit.increment();
+        log::debug!(
+            room = self.room_id.as_str(),
+            conn_id:% = conn_id,
+            nickname,
+            event = "join";
+            "User joined: {}", nickname
+        );
+        self.audit(Event::Join, Some(&conn_id), observer.then_some("observer"));
+
+        self.send_message(&conn_id, OutboundMessage::ResumeToken(token))?;
+        self.send_room_state(&conn_id)?;
+        if self.facilitator.is_none() {
+            self.set_facilitator(&conn_id)?;
+        }
         self.broadcast(&self.users_summary())?;
-        if self.anyone_voted() {
-            self.broadcast(&self.votes_summary())?;
+        if self.round == Round::Revealed || self.anyone_voted() {
+            self.broadcast_votes()?;
         }
 
         Ok(conn_id)
     }
 
-    pub fn disconnect(&mut self, id: &ConnId) -> Result<()> {
-        log::info!(
-            "User disconnected: {}",
-            self.users.get(&id).map_or("<None>", |user| &user.nickname)
-        );
-        self.users.remove(&id);
-        self.broadcast(&self.users_summary())?;
+    pub fn authenticate(&self, credentials: &Credentials) -> Result<()> {
+        auth::authenticate(
+            &self.room_id,
+            self.password.as_deref(),
+            &self.settings.invites,
+            credentials,
+        )
+    }
+
+    /// Sends what a user needs to catch up with the room when joining.
+    fn send_room_state(&self, id: &ConnId) -> Result<()> {
+        let user = self.users.get(id).ok_or(Error::UserNotFound(id.clone()))?;
+
+        self.send_message(id, OutboundMessage::Deck(self.deck.clone()))?;
+        if self.password.is_some() {
+            self.send_message(id, OutboundMessage::RoomProtected(true))?;
+        }
+        if user.status != UserStatus::Active {
+            self.send_message(id, OutboundMessage::YourStatus(user.status.clone()))?;
+        }
+        if !self.stories.is_empty() {
+            self.send_message(id, self.stories_summary())?;
+        }
+        if let Some(timer) = &self.timer {
+            self.send_message(id, OutboundMessage::TimerTick(timer.remaining))?;
+        }
 
         Ok(())
     }
 
-    pub fn vote(&mut self, id: &ConnId, vote: &Vote) -> Result<()> {
-        let max_ord = self.users.values().map(|user| user.ord).max().unwrap_or(0);
-        self.users.get_mut(&id).map(|user| {
-            user.vote(vote.clone());
-            user.ord = max_ord + 1;
-        });
-        self.send_message(id, OutboundMessage::YourVote(vote.clone()))?;
-        self.broadcast(&self.votes_summary())?;
+    /// Keeps a user whose connection dropped, so the session can be resumed
+    /// within the grace period.
+    pub fn suspend(&mut self, id: &ConnId) -> Result<()> {
+        let Some(user) = self.users.get_mut(id) else {
+            log::debug!("User {} already left or resumed elsewhere", id);
+            return Ok(());
+        };
+
+        log::info!(
+            room = self.room_id.as_str(),
+            conn_id:% = id,
+            nickname = user.nickname.as_str(),
+            event = "suspend";
+            "User suspended: {}", user.nickname
+        );
+        user.suspended = Some(Instant::now() + self.settings.resume_grace);
 
-        if self.all_voted() {
-            self.reset_votes();
+        // the room must outlive the sessions while someone may come back
+        if self.keep_alive.is_none() {
+            self.keep_alive = self.weak_cmd_tx.upgrade();
         }
 
         Ok(())
     }
 
-    fn all_voted(&self) -> bool {
-        self.users
-            .values()
-            .all(|user| user.vote.is_valid_vote() || matches!(user.status, UserStatus::Away))
-    }
+    pub fn resume(
+        &mut self,
+        tx: mpsc::UnboundedSender<OutboundMessage>,
+        token: &str,
+    ) -> Result<(ConnId, Nickname)> {
+        let old_id = self
+            .users
+            .iter()
+            .find(|(_, user)| user.token == token)
+            .map(|(id, _)| id.clone())
+            .ok_or(Error::InvalidResumeToken)?;
 
-    fn anyone_voted(&self) -> bool {
-        self.users
-            .values()
-            .any(|user| user.vote.is_valid_vote() && matches!(user.status, UserStatus::Active))
-    }
+        // the previous session may not have noticed its connection is gone yet,
+        // so the user moves to a new id that it doesn't know about
+        let mut user = self.users.remove(&old_id).expect("the user was just found");
+        user.tx = tx;
+        user.suspended = None;
 
-    pub fn set_status(&mut self, id: &ConnId, status: &UserStatus) -> Result<()> {
-        self.users
-            .get_mut(&id)
-            .map(|user| user.status = status.clone());
+        let conn_id = ConnId::new();
+        let nickname = user.nickname.clone();
+        let vote = user.vote.clone();
+        log::info!(
+            room = self.room_id.as_str(),
+            conn_id:% = conn_id,
+            nickname = nickname.as_str(),
+            event = "resume";
+            "User resumed: {}", nickname
+        );
 
-        self.send_message(id, OutboundMessage::YourStatus(status.clone()))?;
+        self.users.insert(conn_id.clone(), user);
+        self.audit(Event::Resume, Some(&conn_id), None);
+        if self.facilitator.as_ref() == Some(&old_id) {
+            self.facilitator = Some(conn_id.clone());
+        }
+        self.release_keep_alive();
+
+        self.send_room_state(&conn_id)?;
+        if vote != Vote::Null {
+            self.send_message(&conn_id, OutboundMessage::YourVote(vote))?;
+        }
         self.broadcast(&self.users_summary())?;
+        if self.round == Round::Revealed || self.anyone_voted() {
+            self.send_message(&conn_id, self.votes_summary())?;
+            if self.round == Round::Revealed {
+                self.send_message(&conn_id, self.stats_summary())?;
+            }
+        }
 
-        Ok(())
+        Ok((conn_id, nickname))
     }
 
-    pub fn users_summary(&self) -> OutboundMessage {
-        let mut users = self
+    pub fn next_suspension_expiry(&self) -> Option<Instant> {
+        self.users.values().filter_map(|user| user.suspended).min()
+    }
+
+    /// Removes the suspended users whose grace period is over.
+    pub fn expire_suspended(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let expired = self
             .users
-            .values()
-            .filter(|user| matches!(user.status, UserStatus::Active))
-            .map(|user| user.nickname.clone())
-            .collect::<Vec<String>>();
+            .iter()
+            .filter(|(_, user)| user.suspended.is_some_and(|deadline| deadline <= now))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
 
-        users.sort();
+        for id in expired {
+            self.disconnect(&id)?;
+        }
+        self.release_keep_alive();
 
-        OutboundMessage::UserList(users)
+        Ok(())
     }
 
-    pub fn votes_summary(&self) -> OutboundMessage {
-        if self.all_voted() {
-            self.vote_result_summary()
-        } else {
-            self.vote_status_summary()
+    fn release_keep_alive(&mut self) {
+        if self.users.values().all(|user| user.suspended.is_none()) {
+            self.keep_alive = None;
         }
     }
 
-    fn vote_status_summary(&self) -> OutboundMessage {
-        let mut statuses = self
-            .users
-            .values()
-            .filter(|user| matches!(user.status, UserStatus::Active))
-            .map(|user| (user.nickname.clone(), user.vote.status(), user.ord))
-            .collect::<Vec<(String, VoteStatus, usize)>>();
-        statuses.sort_by(|(nick_a, status_a, ord_a), (nick_b, status_b, ord_b)| {
-            match (status_a, status_b) {
-                (VoteStatus::NotVoted, VoteStatus::NotVoted) => nick_a.cmp(nick_b),
-                (VoteStatus::NotVoted, _) => Ordering::Greater,
-                (_, VoteStatus::NotVoted) => Ordering::Less,
-                _ => ord_a.cmp(ord_b),
-            }
-        });
+    pub fn disconnect(&mut self, id: &ConnId) -> Result<()> {
+        let nickname = self.users.get(id).map_or("<None>", |user| &user.nickname);
+        log::info!(
+            room = self.room_id.as_str(),
+            conn_id:% = id,
+            nickname,
+            event = "leave";
+            "User disconnected: {}", nickname
+        );
+        self.audit(Event::Leave, Some(id), None);
+        self.remove_user(id)?;
+        self.broadcast(&self.users_summary())?;
 
-        OutboundMessage::VotesStatus(
-            statuses
-                .iter()
-                .map(|(a, b, _)| (a.clone(), b.clone()))
-                .collect(),
-        )
+        if self.should_auto_reveal() {
+            self.reveal_votes()?;
+        }
+
+        Ok(())
     }
 
-    fn vote_result_summary(&self) -> OutboundMessage {
-        let mut votes = self
-            .users
-            .values()
-            .filter(|user| matches!(user.status, UserStatus::Active))
-            .map(|user| (user.nickname.clone(), user.vote.clone(), user.ord))
-            .collect::<Vec<(String, Vote, usize)>>();
-        votes.sort_by(|(_, _, ord_a), (_, _, ord_b)| ord_a.cmp(ord_b));
+    fn remove_user(&mut self, id: &ConnId) -> Result<()> {
+        if self.users.remove(id).is_none() {
+            return Ok(());
+        }
+        self.limit.decrement();
+
+        // the role goes to whoever has been in the room the longest
+        if self.facilitator.as_ref() == Some(id) {
+            self.facilitator = None;
 
-        OutboundMessage::VotesResult(
-            votes
+            let next = self
+                .users
                 .iter()
-                .map(|(a, b, _)| (a.clone(), b.clone()))
-                .collect(),
-        )
+                .min_by_key(|(_, user)| (user.suspended.is_some(), user.joined))
+                .map(|(id, _)| id.clone());
+            if let Some(next) = next {
+                self.set_facilitator(&next)?;
+            }
+        }
+        // kicked or removed while suspended, the user won't come back
+        self.release_keep_alive();
+
+        Ok(())
     }
 
-    fn reset_votes(&mut self) {
-        self.users.iter_mut().for_each(|(_, user)| {
-            user.vote = Vote::Null;
-            user.ord = 0;
-        });
+    /// Hands the role over with a new token, so whoever held it before can't
+    /// claim it back.
+    fn set_facilitator(&mut self, id: &ConnId) -> Result<()> {
+        self.facilitator_token = new_token();
+        self.facilitator = Some(id.clone());
+        self.send_message(
+            id,
+            OutboundMessage::FacilitatorToken(self.facilitator_token.clone()),
+        )
     }
 
-    fn send_to(
-        &self,
-        targets: Vec<&mpsc::UnboundedSender<OutboundMessage>>,
-        message: &OutboundMessage,
-    ) -> Result<()> {
-        for target in targets {
-            target.send(message.clone())?;
+    fn ensure_facilitator(&self, id: &ConnId) -> Result<()> {
+        if self.facilitator.as_ref() != Some(id) {
+            return Err(Error::NotFacilitator);
         }
 
         Ok(())
     }
 
-    pub fn broadcast(&self, message: &OutboundMessage) -> Result<()> {
-        let targets: Vec<_> = self.users.values().map(|user| &user.tx).collect();
-        self.send_to(targets, &message)?;
-
-        Ok(())
+    fn find_by_nickname(&self, nickname: &str) -> Result<ConnId> {
+        self.users
+            .iter()
+            .find(|(_, user)| user.nickname == nickname)
+            .map(|(id, _)| id.clone())
+            .ok_or_else(|| Error::NicknameNotFound(nickname.into()))
     }
 
-    pub fn send_message(&self, id: &ConnId, message: OutboundMessage) -> Result<()> {
-        let user = self.users.get(&id).ok_or(Error::UserNotFound(id.clone()))?;
-        self.send_to(vec![&user.tx], &message)?;
+    pub fn claim(&mut self, id: &ConnId, token: &str) -> Result<()> {
+        if token != self.facilitator_token {
+            return Err(Error::InvalidFacilitatorToken);
+        }
+
+        self.set_facilitator(id)?;
+        self.broadcast(&self.users_summary())?;
+        self.audit(Event::Claim, Some(id), None);
 
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio::sync::{mpsc, oneshot};
+    pub fn promote(&mut self, id: &ConnId, nickname: &str) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        let target = self.find_by_nickname(nickname)?;
 
-    fn setup_test_server() -> (
-        Arc<Mutex<GameServer>>,
-        GameHandle,
-        actix_rt::task::JoinHandle<()>,
-    ) {
-        let (server, handle) = GameServer::new();
-        let server = Arc::new(Mutex::new(server));
+        self.set_facilitator(&target)?;
+        self.broadcast(&self.users_summary())?;
+        self.audit(Event::Promote, Some(id), Some(nickname));
 
-        let server_clone = Arc::clone(&server);
-        let server_task = tokio::spawn(async move {
-            let mut server = server_clone.lock().await;
+        Ok(())
+    }
 
-            server.run().await;
-        });
+    pub fn kick(&mut self, id: &ConnId, nickname: &str) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        let target = self.find_by_nickname(nickname)?;
 
-        (server, handle, server_task)
+        self.audit(Event::Kick, Some(id), Some(nickname));
+        self.kick_user(&target)
     }
 
-    async fn shutdown_test_server(
-        handle: &GameHandle,
-        server_task: actix_rt::task::JoinHandle<()>,
-    ) {
-        handle.cmd_tx.send(Command::Shutdown).unwrap();
-        server_task.await.expect("Server task did not complete");
+    /// Kicks a user on behalf of the operators.
+    pub fn remove(&mut self, target: &ConnId) -> Result<()> {
+        let nickname = self
+            .users
+            .get(target)
+            .map(|user| user.nickname.clone())
+            .ok_or(Error::UserNotFound(target.clone()))?;
+
+        self.audit(Event::Kick, None, Some(&nickname));
+        self.kick_user(target)
     }
 
-    async fn connect_user(nickname: &str, handle: &GameHandle) -> Result<ConnId> {
-        let (tx, _rx) = mpsc::unbounded_channel();
-        let (res_tx, res_rx) = oneshot::channel();
+    fn kick_user(&mut self, target: &ConnId) -> Result<()> {
+        let nickname = self
+            .users
+            .get(target)
+            .map_or("<None>", |user| &user.nickname);
+        log::info!(
+            room = self.room_id.as_str(),
+            conn_id:% = target,
+            nickname,
+            event = "kick";
+            "User kicked: {}", nickname
+        );
+        self.send_message(target, OutboundMessage::Kicked)?;
+        self.remove_user(target)?;
+        self.broadcast(&self.users_summary())?;
 
-        handle
-            .cmd_tx
-            .send(Command::Connect {
-                conn_tx: tx,
-                nickname: nickname.into(),
-                res_tx: Some(res_tx),
-            })
-            .unwrap();
+        if self.should_auto_reveal() {
+            self.reveal_votes()?;
+        }
 
-        res_rx.await.unwrap()
+        Ok(())
     }
 
-    use std::sync::Arc;
-    use tokio::sync::Mutex;
+    pub fn force_away(&mut self, id: &ConnId, nickname: &str) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        let target = self.find_by_nickname(nickname)?;
 
-    #[tokio::test]
-    async fn test_user_connection() {
+        self.audit(Event::ForceAway, Some(id), Some(nickname));
+        self.set_status(&target, &UserStatus::Away)
+    }
+
+    pub fn set_locked(&mut self, id: &ConnId, locked: bool) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.locked = locked;
+        self.broadcast(&OutboundMessage::RoomLocked(locked))?;
+        self.audit(
+            if locked { Event::Lock } else { Event::Unlock },
+            Some(id),
+            None,
+        );
+
+        Ok(())
+    }
+
+    pub fn set_password(&mut self, id: &ConnId, password: &Option<String>) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.password = match password.as_deref().map(str::trim) {
+            Some("") => return Err(Error::PasswordRequired),
+            Some(password) => Some(auth::hash_password(password)),
+            None => None,
+        };
+        self.dirty = true;
+        self.broadcast(&OutboundMessage::RoomProtected(self.password.is_some()))?;
+        self.audit(
+            if self.password.is_some() {
+                Event::SetPassword
+            } else {
+                Event::RemovePassword
+            },
+            Some(id),
+            None,
+        );
+
+        Ok(())
+    }
+
+    pub fn create_invite(&self, id: &ConnId, minutes: u64) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        if minutes == 0 || minutes > MAX_INVITE_MINUTES {
+            return Err(Error::InvalidInviteLifetime(MAX_INVITE_MINUTES));
+        }
+
+        let invite = self
+            .settings
+            .invites
+            .create(&self.room_id, Duration::from_secs(minutes * 60));
+        self.send_message(
+            id,
+            OutboundMessage::Invite {
+                link: format!("/r/{}?invite={}", self.room_id, invite),
+                minutes,
+            },
+        )
+    }
+
+    pub fn vote(&mut self, id: &ConnId, vote: &Vote) -> Result<()> {
+        if self
+            .users
+            .get(id)
+            .is_some_and(|user| user.status == UserStatus::Observer)
+        {
+            return Err(Error::ObserverCannotVote);
+        }
+
+        if self.round == Round::Revealed {
+            return Err(Error::RoundAlreadyRevealed);
+        }
+
+        if *vote != Vote::Null && !self.deck.contains(vote) {
+            return Err(Error::InvalidVote(vote.to_string()));
+        }
+
+        let max_ord = self.users.values().map(|user| user.ord).max().unwrap_or(0);
+        if let Some(user) = self.users.get_mut(id) {
+            user.vote(vote.clone());
+            user.ord = max_ord + 1;
+        }
+        self.send_message(id, OutboundMessage::YourVote(vote.clone()))?;
+        self.audit(Event::Vote, Some(id), Some(&vote.to_string()));
+        if let Some((_, metrics)) = &self.metrics {
+            if *vote != Vote::Null {
+                metrics.vote_cast();
+            }
+        }
+
+        if self.should_auto_reveal() {
+            self.reveal_votes()?;
+        } else {
+            self.broadcast(&self.votes_summary())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn reveal(&mut self, id: &ConnId) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.reveal_votes_by(Some(id))
+    }
+
+    /// Reveals the votes on its own, e.g. when everyone voted or the timer ran out.
+    fn reveal_votes(&mut self) -> Result<()> {
+        self.reveal_votes_by(None)
+    }
+
+    fn reveal_votes_by(&mut self, by: Option<&ConnId>) -> Result<()> {
+        if self.round == Round::Revealed {
+            return Err(Error::RoundAlreadyRevealed);
+        }
+
+        self.round = Round::Revealed;
+        self.audit(Event::Reveal, by, None);
+        self.broadcast_votes()?;
+
+        Ok(())
+    }
+
+    /// Broadcasts the votes, along with their stats once they are revealed.
+    fn broadcast_votes(&self) -> Result<()> {
+        self.broadcast(&self.votes_summary())?;
+
+        if self.round == Round::Revealed {
+            self.broadcast(&self.stats_summary())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn next_round(&mut self, id: &ConnId) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.archive_round(None);
+        self.reset_votes();
+        self.broadcast(&self.vote_status_summary())?;
+
+        Ok(())
+    }
+
+    /// Keeps the outcome of a revealed round before its votes are reset.
+    fn archive_round(&mut self, estimate: Option<Vote>) {
+        if self.round != Round::Revealed {
+            return;
+        }
+
+        let story = self.current_story.as_ref().and_then(|key| {
+            self.stories
+                .iter()
+                .find(|story| story.key == *key)
+                .map(|story| Story {
+                    estimate: None,
+                    ..story.clone()
+                })
+        });
+
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.dirty = true;
+        self.history.push(RoundRecord {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            story,
+            votes: self.revealed_votes(),
+            estimate,
+        });
+
+        if let Some((_, metrics)) = &self.metrics {
+            metrics.round_completed();
+        }
+    }
+
+    pub fn history(&self) -> Vec<RoundRecord> {
+        self.history.clone()
+    }
+
+    pub fn clear_votes(&mut self, id: &ConnId) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.reset_votes();
+        self.broadcast(&self.vote_status_summary())?;
+        self.audit(Event::ClearVotes, Some(id), None);
+
+        Ok(())
+    }
+
+    pub fn set_auto_reveal(&mut self, id: &ConnId, auto_reveal: bool) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        self.auto_reveal = auto_reveal;
+        self.dirty = true;
+        self.broadcast(&OutboundMessage::AutoReveal(auto_reveal))?;
+
+        if self.should_auto_reveal() {
+            self.reveal_votes()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn shutdown(&mut self, message: Option<String>) -> Result<()> {
+        log::info!("Game shutting down");
+
+        self.timer = None;
+        self.broadcast(&OutboundMessage::ShuttingDown(message))
+    }
+
+    /// Sends everyone away for good; the game server stops right after.
+    pub fn close(&mut self) -> Result<()> {
+        log::info!(room = self.room_id.as_str(), event = "close"; "Room closed");
+
+        self.timer = None;
+        self.audit(Event::Close, None, None);
+        self.broadcast(&OutboundMessage::RoomClosed)
+    }
+
+    pub fn notice(&self, message: &str) -> Result<()> {
+        self.audit(Event::Notice, None, Some(message));
+        self.broadcast(&OutboundMessage::Notice(message.to_string()))
+    }
+
+    pub fn info(&self) -> RoomInfo {
+        let mut users = self
+            .users
+            .iter()
+            .map(|(id, user)| UserInfo {
+                conn_id: id.to_string(),
+                nickname: user.nickname.clone(),
+                status: user.status.clone(),
+                facilitator: self.facilitator.as_ref() == Some(id),
+                voted: user.vote != Vote::Null,
+                suspended: user.suspended.is_some(),
+            })
+            .collect::<Vec<_>>();
+        users.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+
+        RoomInfo {
+            room: self.room_id.clone(),
+            deck: self.deck.clone(),
+            locked: self.locked,
+            protected: self.password.is_some(),
+            current_story: self.current_story.clone(),
+            users,
+        }
+    }
+
+    pub fn round_info(&self) -> RoundInfo {
+        let OutboundMessage::VotesStatus { votes, story } = self.vote_status_summary() else {
+            unreachable!("the vote status summary is always a VotesStatus");
+        };
+
+        let revealed = self.round == Round::Revealed;
+        RoundInfo {
+            revealed,
+            story,
+            votes,
+            results: revealed.then(|| self.revealed_votes()),
+            stats: revealed.then(|| VoteStats::compute(&self.revealed_votes(), &self.deck)),
+        }
+    }
+
+    pub fn start_timer(&mut self, id: &ConnId, seconds: u64, reveal: bool) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        if seconds == 0 || seconds > MAX_TIMER_SECONDS {
+            return Err(Error::InvalidTimer(MAX_TIMER_SECONDS));
+        }
+
+        self.timer = Some(Timer {
+            remaining: seconds,
+            next_tick: Instant::now() + TIMER_TICK,
+            reveal,
+        });
+        self.broadcast(&OutboundMessage::TimerStarted { seconds, reveal })?;
+
+        Ok(())
+    }
+
+    pub fn stop_timer(&mut self, id: &ConnId) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        if self.timer.take().is_none() {
+            return Err(Error::NoTimerRunning);
+        }
+        self.broadcast(&OutboundMessage::TimerStopped)?;
+
+        Ok(())
+    }
+
+    pub fn next_timer_tick(&self) -> Option<Instant> {
+        self.timer.as_ref().map(|timer| timer.next_tick)
+    }
+
+    /// Counts the running timer down by one tick, revealing the votes on
+    /// expiry when the timer was started with `reveal`.
+    pub fn tick_timer(&mut self) -> Result<()> {
+        let Some(timer) = self.timer.as_mut() else {
+            return Ok(());
+        };
+
+        timer.remaining -= 1;
+        if timer.remaining > 0 {
+            timer.next_tick += TIMER_TICK;
+            let remaining = timer.remaining;
+
+            return self.broadcast(&OutboundMessage::TimerTick(remaining));
+        }
+
+        let reveal = timer.reveal;
+        self.timer = None;
+        self.broadcast(&OutboundMessage::TimerExpired)?;
+
+        if reveal && self.round == Round::Voting {
+            self.reveal_votes()?;
+        }
+
+        Ok(())
+    }
+
+    fn should_auto_reveal(&self) -> bool {
+        self.auto_reveal && self.round == Round::Voting && self.anyone_voted() && self.all_voted()
+    }
+
+    fn all_voted(&self) -> bool {
+        self.users
+            .values()
+            .all(|user| user.vote.is_valid_vote() || user.status != UserStatus::Active)
+    }
+
+    fn anyone_voted(&self) -> bool {
+        self.users
+            .values()
+            .any(|user| user.vote.is_valid_vote() && matches!(user.status, UserStatus::Active))
+    }
+
+    pub fn set_status(&mut self, id: &ConnId, status: &UserStatus) -> Result<()> {
+        if let Some(user) = self.users.get_mut(id) {
+            user.status = status.clone();
+        }
+
+        self.send_message(id, OutboundMessage::YourStatus(status.clone()))?;
+        self.broadcast(&self.users_summary())?;
+
+        if self.should_auto_reveal() {
+            self.reveal_votes()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the nickname the user ends up with, trimmed and truncated
+    /// like on joining.
+    pub fn set_nickname(&mut self, id: &ConnId, nickname: &str) -> Result<Nickname> {
+        let nickname =
+            validate_nickname(nickname, &self.users, self.settings.max_nickname_len)?.to_string();
+
+        self.audit(Event::Rename, Some(id), Some(&nickname));
+        let user = self
+            .users
+            .get_mut(id)
+            .ok_or(Error::UserNotFound(id.clone()))?;
+        log::info!(
+            room = self.room_id.as_str(),
+            conn_id:% = id,
+            nickname = nickname.as_str(),
+            event = "rename";
+            "User renamed: {} to {}", user.nickname, nickname
+        );
+        user.nickname = nickname.clone();
+
+        self.broadcast(&self.users_summary())?;
+        if self.round == Round::Revealed || self.anyone_voted() {
+            self.broadcast_votes()?;
+        }
+
+        Ok(nickname)
+    }
+
+    pub fn set_deck(&mut self, id: &ConnId, deck: &Deck) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        deck.validate().map_err(Error::InvalidDeck)?;
+
+        let anyone_voted = self.round == Round::Revealed || self.anyone_voted();
+        self.deck = deck.clone();
+        self.dirty = true;
+        self.archive_round(None);
+        self.reset_votes();
+
+        self.broadcast(&OutboundMessage::Deck(deck.clone()))?;
+        if anyone_voted {
+            self.broadcast(&self.votes_summary())?;
+        }
+
+        Ok(())
+    }
+
+    fn find_story(&mut self, key: &str) -> Result<&mut Story> {
+        self.stories
+            .iter_mut()
+            .find(|story| story.key == key)
+            .ok_or_else(|| Error::StoryNotFound(key.into()))
+    }
+
+    pub fn add_story(&mut self, id: &ConnId, story: &Story) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        story.validate().map_err(Error::InvalidStory)?;
+
+        if self.find_story(&story.key).is_ok() {
+            return Err(Error::InvalidStory(format!(
+                "{} is already in the queue",
+                story.key
+            )));
+        }
+
+        self.stories.push(story.clone());
+        if self.current_story.is_none() {
+            self.current_story = Some(story.key.clone());
+        }
+        self.dirty = true;
+        self.broadcast(&self.stories_summary())?;
+
+        Ok(())
+    }
+
+    pub fn edit_story(
+        &mut self,
+        id: &ConnId,
+        key: &str,
+        title: &Option<String>,
+        link: &Option<String>,
+        description: &Option<String>,
+    ) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        let mut story = self.find_story(key)?.clone();
+        if let Some(title) = title {
+            story.title = title.clone();
+        }
+        // an empty value clears the field
+        if let Some(link) = link {
+            story.link = Some(link.clone()).filter(|link| !link.is_empty());
+        }
+        if let Some(description) = description {
+            story.description = Some(description.clone()).filter(|text| !text.is_empty());
+        }
+        story.validate().map_err(Error::InvalidStory)?;
+
+        *self.find_story(key)? = story;
+        self.dirty = true;
+        self.broadcast(&self.stories_summary())?;
+
+        Ok(())
+    }
+
+    pub fn move_story(&mut self, id: &ConnId, key: &str, position: usize) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        let story = self.find_story(key)?.clone();
+        self.stories.retain(|story| story.key != key);
+
+        // positions start at 1, as shown to the users
+        let index = position.saturating_sub(1).min(self.stories.len());
+        self.stories.insert(index, story);
+        self.dirty = true;
+        self.broadcast(&self.stories_summary())?;
+
+        Ok(())
+    }
+
+    pub fn remove_story(&mut self, id: &ConnId, key: &str) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        self.find_story(key)?;
+
+        if self.current_story.as_deref() == Some(key) {
+            self.current_story = self.next_story(key);
+        }
+        self.stories.retain(|story| story.key != key);
+        self.dirty = true;
+        self.broadcast(&self.stories_summary())?;
+
+        Ok(())
+    }
+
+    pub fn select_story(&mut self, id: &ConnId, key: &str) -> Result<()> {
+        self.ensure_facilitator(id)?;
+        self.find_story(key)?;
+
+        self.archive_round(None);
+        self.current_story = Some(key.to_string());
+        self.dirty = true;
+        self.reset_votes();
+        self.broadcast(&self.stories_summary())?;
+        self.broadcast(&self.vote_status_summary())?;
+
+        Ok(())
+    }
+
+    pub fn list_stories(&self, id: &ConnId) -> Result<()> {
+        self.send_message(id, self.stories_summary())
+    }
+
+    pub fn who(&self, id: &ConnId) -> Result<()> {
+        self.send_message(id, self.users_summary())
+    }
+
+    pub fn status(&self, id: &ConnId) -> Result<()> {
+        let user = self.users.get(id).ok_or(Error::UserNotFound(id.clone()))?;
+
+        self.send_message(
+            id,
+            OutboundMessage::Status {
+                room: self.room_id.clone(),
+                nickname: user.nickname.clone(),
+                status: user.status.clone(),
+                vote: user.vote.clone(),
+                facilitator: self.facilitator.as_ref() == Some(id),
+                revealed: self.round == Round::Revealed,
+            },
+        )
+    }
+
+    pub fn accept(&mut self, id: &ConnId, estimate: &Vote) -> Result<()> {
+        self.ensure_facilitator(id)?;
+
+        if self.round != Round::Revealed {
+            return Err(Error::RoundNotRevealed);
+        }
+        if !estimate.is_valid_vote() {
+            return Err(Error::InvalidEstimate);
+        }
+        let key = self.current_story.clone().ok_or(Error::NoCurrentStory)?;
+
+        log::info!("Story estimated: {} = {}", key, estimate);
+        self.archive_round(Some(estimate.clone()));
+        self.find_story(&key)?.estimate = Some(estimate.clone());
+
+        // move on to the next story still waiting for an estimate
+        self.current_story = self.next_story(&key);
+        self.dirty = true;
+        self.reset_votes();
+        self.broadcast(&self.stories_summary())?;
+        self.broadcast(&self.vote_status_summary())?;
+
+        Ok(())
+    }
+
+    fn next_story(&self, key: &str) -> Option<String> {
+        let position = self.stories.iter().position(|story| story.key == key)?;
+
+        self.stories[position + 1..]
+            .iter()
+            .chain(&self.stories[..position])
+            .find(|story| story.estimate.is_none())
+            .map(|story| story.key.clone())
+    }
+
+    pub fn stories_summary(&self) -> OutboundMessage {
+        OutboundMessage::Stories {
+            stories: self.stories.clone(),
+            current: self.current_story.clone(),
+        }
+    }
+
+    pub fn users_summary(&self) -> OutboundMessage {
+        let mut users = self
+            .users
+            .iter()
+            .filter(|(_, user)| user.status != UserStatus::Away)
+            .map(|(id, user)| Participant {
+                nickname: user.nickname.clone(),
+                facilitator: self.facilitator.as_ref() == Some(id),
+                observer: user.status == UserStatus::Observer,
+            })
+            .collect::<Vec<Participant>>();
+
+        users.sort_by(|a, b| a.nickname.cmp(&b.nickname));
+
+        OutboundMessage::UserList(users)
+    }
+
+    pub fn votes_summary(&self) -> OutboundMessage {
+        match self.round {
+            Round::Revealed => self.vote_result_summary(),
+            Round::Voting => self.vote_status_summary(),
+        }
+    }
+
+    pub fn stats_summary(&self) -> OutboundMessage {
+        OutboundMessage::Stats(VoteStats::compute(&self.revealed_votes(), &self.deck))
+    }
+
+    fn vote_status_summary(&self) -> OutboundMessage {
+        let mut statuses = self
+            .users
+            .values()
+            .filter(|user| matches!(user.status, UserStatus::Active))
+            .map(|user| (user.nickname.clone(), user.vote.status(), user.ord))
+            .collect::<Vec<(String, VoteStatus, usize)>>();
+        statuses.sort_by(|(nick_a, status_a, ord_a), (nick_b, status_b, ord_b)| {
+            match (status_a, status_b) {
+                (VoteStatus::NotVoted, VoteStatus::NotVoted) => nick_a.cmp(nick_b),
+                (VoteStatus::NotVoted, _) => Ordering::Greater,
+                (_, VoteStatus::NotVoted) => Ordering::Less,
+                _ => ord_a.cmp(ord_b),
+            }
+        });
+
+        OutboundMessage::VotesStatus {
+            votes: statuses
+                .iter()
+                .map(|(a, b, _)| (a.clone(), b.clone()))
+                .collect(),
+            story: self.current_story.clone(),
+        }
+    }
+
+    fn vote_result_summary(&self) -> OutboundMessage {
+        OutboundMessage::VotesResult {
+            votes: self.revealed_votes(),
+            story: self.current_story.clone(),
+        }
+    }
+
+    fn revealed_votes(&self) -> Vec<(String, Vote)> {
+        let mut votes = self
+            .users
+            .values()
+            .filter(|user| matches!(user.status, UserStatus::Active))
+            .map(|user| (user.nickname.clone(), user.vote.clone(), user.ord))
+            .collect::<Vec<(String, Vote, usize)>>();
+        // users who didn't vote before an early reveal go last
+        votes.sort_by(|(nick_a, vote_a, ord_a), (nick_b, vote_b, ord_b)| {
+            match (vote_a.is_valid_vote(), vote_b.is_valid_vote()) {
+                (false, false) => nick_a.cmp(nick_b),
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                _ => ord_a.cmp(ord_b),
+            }
+        });
+
+        votes
+            .iter()
+            .map(|(a, b, _)| (a.clone(), b.clone()))
+            .collect()
+    }
+
+    fn reset_votes(&mut self) {
+        self.round = Round::Voting;
+        self.users.iter_mut().for_each(|(_, user)| {
+            user.vote = Vote::Null;
+            user.ord = 0;
+        });
+    }
+
+    fn send_to(
+        &self,
+        targets: Vec<&mpsc::UnboundedSender<OutboundMessage>>,
+        message: &OutboundMessage,
+    ) -> Result<()> {
+        for target in targets {
+            target.send(message.clone())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn broadcast(&self, message: &OutboundMessage) -> Result<()> {
+        let targets: Vec<_> = self
+            .users
+            .values()
+            .filter(|user| user.suspended.is_none())
+            .map(|user| &user.tx)
+            .collect();
+        self.send_to(targets, message)?;
+
+        Ok(())
+    }
+
+    pub fn send_message(&self, id: &ConnId, message: OutboundMessage) -> Result<()> {
+        let user = self.users.get(id).ok_or(Error::UserNotFound(id.clone()))?;
+        if user.suspended.is_some() {
+            return Ok(());
+        }
+        self.send_to(vec![&user.tx], &message)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::{mpsc, oneshot};
+
+    fn setup_test_server() -> (
+        Arc<Mutex<GameServer>>,
+        GameHandle,
+        actix_rt::task::JoinHandle<()>,
+    ) {
+        setup_limited_test_server(Limit::default())
+    }
+
+    fn setup_limited_test_server(
+        limit: Limit,
+    ) -> (
+        Arc<Mutex<GameServer>>,
+        GameHandle,
+        actix_rt::task::JoinHandle<()>,
+    ) {
+        let (server, handle) = GameServer::new("test", limit, RoomSettings::default());
+        let server = Arc::new(Mutex::new(server));
+
+        let server_clone = Arc::clone(&server);
+        let server_task = tokio::spawn(async move {
+            let mut server = server_clone.lock().await;
+
+            server.run().await;
+        });
+
+        (server, handle, server_task)
+    }
+
+    async fn shutdown_test_server(
+        handle: &GameHandle,
+        server_task: actix_rt::task::JoinHandle<()>,
+    ) {
+        handle
+            .send(Command::Shutdown {
+                message: None,
+                res_tx: None,
+            })
+            .unwrap();
+        server_task.await.expect("Server task did not complete");
+    }
+
+    async fn connect_user(nickname: &str, handle: &GameHandle) -> Result<ConnId> {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (res_tx, res_rx) = oneshot::channel();
+
+        handle
+            .send(Command::Connect {
+                conn_tx: tx,
+                nickname: nickname.into(),
+                observer: false,
+                credentials: Credentials::default(),
+                res_tx: Some(res_tx),
+            })
+            .unwrap();
+
+        res_rx.await.unwrap()
+    }
+
+    async fn connect_listening_user(
+        nickname: &str,
+        handle: &GameHandle,
+    ) -> (ConnId, mpsc::UnboundedReceiver<OutboundMessage>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let conn_id = handle
+            .connect(tx, nickname, false, &Credentials::default())
+            .await
+            .unwrap();
+
+        (conn_id, rx)
+    }
+
+    fn last_message(rx: &mut mpsc::UnboundedReceiver<OutboundMessage>) -> OutboundMessage {
+        let mut last = None;
+        while let Ok(message) = rx.try_recv() {
+            last = Some(message);
+        }
+
+        last.expect("no message received")
+    }
+
+    /// The latest result received, which must be followed by its stats.
+    fn result_message(rx: &mut mpsc::UnboundedReceiver<OutboundMessage>) -> OutboundMessage {
+        let mut messages = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            messages.push(message);
+        }
+
+        let position = messages
+            .iter()
+            .rposition(|message| matches!(message, OutboundMessage::VotesResult { .. }))
+            .expect("no result received");
+        assert!(matches!(
+            messages.get(position + 1),
+            Some(OutboundMessage::Stats(_))
+        ));
+
+        messages.swap_remove(position)
+    }
+
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[test]
+    fn test_validate_nickname() {
+        let users = UsersMap::new();
+
+        assert_eq!(
+            validate_nickname(" Player1 ", &users, 20).unwrap(),
+            "Player1"
+        );
+        assert_eq!(
+            validate_nickname("ÁéÍóÚ ÁéÍóÚ", &users, 7).unwrap(),
+            "ÁéÍóÚ Á"
+        );
+        assert!(matches!(
+            validate_nickname(" ", &users, 20),
+            Err(Error::NicknameCannotBeEmpty)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_user_connection() {
+        let (server, handle, server_task) = setup_test_server();
+        let conn_id = connect_user("Player1", &handle).await;
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+
+        let server = server.lock().await;
+        assert!(server.users.contains_key(&conn_id.unwrap()));
+        assert_eq!(
+            server.users_summary(),
+            OutboundMessage::UserList(vec![Participant {
+                nickname: "Player1".into(),
+                facilitator: true,
+                observer: false,
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_disconnection() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let conn_id = connect_user("Player1", &handle).await.unwrap();
+
+        handle
+            .send(Command::Disconnect {
+                conn_id: conn_id.clone(),
+                res_tx: None,
+            })
+            .unwrap();
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert!(!server.users.contains_key(&conn_id));
+        assert_eq!(server.users_summary(), OutboundMessage::UserList(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_voting() {
         let (server, handle, server_task) = setup_test_server();
-        let conn_id = connect_user("Player1", &handle).await;
+
+        let conn_id = connect_user("Player1", &handle).await.unwrap();
+        let _ = connect_user("Player2", &handle).await;
+
+        let vote = Vote::from(2);
+
+        handle
+            .send(Command::Vote {
+                conn_id: conn_id.clone(),
+                vote: vote.clone(),
+                res_tx: None,
+            })
+            .unwrap();
 
         // unlock the server
         shutdown_test_server(&handle, server_task).await;
-
         let server = server.lock().await;
-        assert!(server.users.contains_key(&conn_id.unwrap()));
+
+        assert_eq!(server.users.get(&conn_id).unwrap().vote, Vote::from(2));
         assert_eq!(
-            server.users_summary(),
-            OutboundMessage::UserList(vec!["Player1".into()])
+            server.votes_summary(),
+            OutboundMessage::VotesStatus {
+                votes: vec![
+                    ("Player1".into(), VoteStatus::Voted),
+                    ("Player2".into(), VoteStatus::NotVoted),
+                ],
+                story: None
+            }
         );
     }
 
     #[tokio::test]
-    async fn test_user_disconnection() {
-        let (server, handle, server_task) = setup_test_server();
+    async fn test_room_limit() {
+        let (server, handle, server_task) = setup_limited_test_server(Limit::new(1));
 
         let conn_id = connect_user("Player1", &handle).await.unwrap();
+        let result = connect_user("Player2", &handle).await;
+        assert!(matches!(result, Err(Error::RoomIsFull)));
 
         handle
-            .cmd_tx
             .send(Command::Disconnect {
-                conn_id: conn_id.clone(),
+                conn_id,
                 res_tx: None,
             })
             .unwrap();
+        assert!(connect_user("Player2", &handle).await.is_ok());
 
         // unlock the server
         shutdown_test_server(&handle, server_task).await;
         let server = server.lock().await;
 
-        assert!(!server.users.contains_key(&conn_id));
-        assert_eq!(server.users_summary(), OutboundMessage::UserList(vec![]));
+        assert_eq!(
+            server.users_summary(),
+            OutboundMessage::UserList(vec![Participant {
+                nickname: "Player2".into(),
+                facilitator: true,
+                observer: false,
+            }])
+        );
     }
 
     #[tokio::test]
-    async fn test_voting() {
+    async fn test_deck() {
         let (server, handle, server_task) = setup_test_server();
 
         let conn_id = connect_user("Player1", &handle).await.unwrap();
         let _ = connect_user("Player2", &handle).await;
 
-        let vote = Vote::Option(2);
+        for vote in ["XL", "M"] {
+            handle
+                .send(Command::Vote {
+                    conn_id: conn_id.clone(),
+                    vote: Vote::from(vote),
+                    res_tx: None,
+                })
+                .unwrap();
+
+            if vote == "XL" {
+                handle
+                    .send(Command::SetDeck {
+                        conn_id: conn_id.clone(),
+                        deck: Deck::t_shirt(),
+                        res_tx: None,
+                    })
+                    .unwrap();
+            }
+        }
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.deck, Deck::t_shirt());
+        assert_eq!(server.users.get(&conn_id).unwrap().vote, Vote::from("M"));
+    }
+
+    #[tokio::test]
+    async fn test_nickname_and_status() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        // cards outside of the deck are turned down
+        let result = vote(&conn_id_2, Vote::from("XL"), &handle).await;
+        assert!(matches!(result, Err(Error::InvalidVote(vote)) if vote == "XL"));
+        vote(&conn_id_2, Vote::from(5), &handle).await.unwrap();
+
+        let result = handle.set_nickname(&conn_id_2, "Player1").await;
+        assert!(matches!(result, Err(Error::NicknameAlreadyInUse(_))));
+        let nickname = handle.set_nickname(&conn_id_2, " Player3 ").await.unwrap();
+        assert_eq!(nickname, "Player3");
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::VotesStatus {
+                votes: vec![
+                    ("Player3".into(), VoteStatus::Voted),
+                    ("Player1".into(), VoteStatus::NotVoted),
+                ],
+                story: None
+            }
+        );
+
+        handle.who(&conn_id_1).await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::UserList(vec![
+                Participant {
+                    nickname: "Player1".into(),
+                    facilitator: true,
+                    observer: false,
+                },
+                Participant {
+                    nickname: "Player3".into(),
+                    facilitator: false,
+                    observer: false,
+                },
+            ])
+        );
+
+        handle.status(&conn_id_1).await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::Status {
+                room: "test".into(),
+                nickname: "Player1".into(),
+                status: UserStatus::Active,
+                vote: Vote::Null,
+                facilitator: true,
+                revealed: false,
+            }
+        );
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.users.get(&conn_id_2).unwrap().nickname, "Player3");
+    }
+
+    async fn vote(conn_id: &ConnId, vote: Vote, handle: &GameHandle) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
 
         handle
-            .cmd_tx
             .send(Command::Vote {
                 conn_id: conn_id.clone(),
-                vote: vote.clone(),
-                res_tx: None,
+                vote,
+                res_tx: Some(res_tx),
             })
             .unwrap();
 
+        res_rx.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reveal_and_next_round() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        vote(&conn_id_1, Vote::from(3), &handle).await.unwrap();
+        handle.reveal(&conn_id_1).await.unwrap();
+        assert_eq!(
+            result_message(&mut rx_1),
+            OutboundMessage::VotesResult {
+                votes: vec![
+                    ("Player1".into(), Vote::from(3)),
+                    ("Player2".into(), Vote::Null),
+                ],
+                story: None
+            }
+        );
+
+        let result = vote(&conn_id_2, Vote::from(5), &handle).await;
+        assert!(matches!(result, Err(Error::RoundAlreadyRevealed)));
+
+        handle.next_round(&conn_id_1).await.unwrap();
+        vote(&conn_id_2, Vote::from(5), &handle).await.unwrap();
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.round, Round::Voting);
+        assert_eq!(
+            server.votes_summary(),
+            OutboundMessage::VotesStatus {
+                votes: vec![
+                    ("Player2".into(), VoteStatus::Voted),
+                    ("Player1".into(), VoteStatus::NotVoted),
+                ],
+                story: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_reveal() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        handle.set_auto_reveal(&conn_id_1, false).await.unwrap();
+        vote(&conn_id_1, Vote::from(3), &handle).await.unwrap();
+        vote(&conn_id_2, Vote::Unknown, &handle).await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::VotesStatus {
+                votes: vec![
+                    ("Player1".into(), VoteStatus::Voted),
+                    ("Player2".into(), VoteStatus::Voted),
+                ],
+                story: None
+            }
+        );
+
+        handle.reveal(&conn_id_1).await.unwrap();
+
         // unlock the server
         shutdown_test_server(&handle, server_task).await;
         let server = server.lock().await;
 
-        assert_eq!(server.users.get(&conn_id).unwrap().vote, Vote::Option(2));
+        assert_eq!(server.round, Round::Revealed);
+        assert!(matches!(
+            server.stats_summary(),
+            OutboundMessage::Stats(VoteStats {
+                unknown: 1,
+                consensus: false,
+                ..
+            })
+        ));
         assert_eq!(
             server.votes_summary(),
-            OutboundMessage::VotesStatus(vec![
-                ("Player1".into(), VoteStatus::Voted),
-                ("Player2".into(), VoteStatus::NotVoted),
+            OutboundMessage::VotesResult {
+                votes: vec![
+                    ("Player1".into(), Vote::from(3)),
+                    ("Player2".into(), Vote::Unknown),
+                ],
+                story: None
+            }
+        );
+    }
+
+    fn facilitator_token(rx: &mut mpsc::UnboundedReceiver<OutboundMessage>) -> String {
+        let mut token = None;
+        while let Ok(message) = rx.try_recv() {
+            if let OutboundMessage::FacilitatorToken(value) = message {
+                token = Some(value);
+            }
+        }
+
+        token.expect("no facilitator token received")
+    }
+
+    #[tokio::test]
+    async fn test_facilitator_only_commands() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, mut rx_2) = connect_listening_user("Player2", &handle).await;
+
+        assert!(matches!(
+            handle.reveal(&conn_id_2).await,
+            Err(Error::NotFacilitator)
+        ));
+        assert!(matches!(
+            handle.set_deck(&conn_id_2, &Deck::t_shirt()).await,
+            Err(Error::NotFacilitator)
+        ));
+        assert!(matches!(
+            handle.kick(&conn_id_2, "Player1").await,
+            Err(Error::NotFacilitator)
+        ));
+        assert!(matches!(
+            handle.force_away(&conn_id_1, "Player3").await,
+            Err(Error::NicknameNotFound(_))
+        ));
+
+        handle.force_away(&conn_id_1, "Player2").await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_2),
+            OutboundMessage::UserList(vec![Participant {
+                nickname: "Player1".into(),
+                facilitator: true,
+                observer: false,
+            }])
+        );
+
+        handle.set_locked(&conn_id_1, true).await.unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert!(matches!(
+            handle
+                .connect(tx, "Player3", false, &Credentials::default())
+                .await,
+            Err(Error::RoomIsLocked)
+        ));
+
+        handle.kick(&conn_id_1, "Player2").await.unwrap();
+        assert_eq!(last_message(&mut rx_2), OutboundMessage::Kicked);
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert!(!server.users.contains_key(&conn_id_2));
+        assert_eq!(server.users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let path = std::env::temp_dir().join(format!("omnipoker-{}.log", Uuid::new_v4()));
+        let (mut server, handle) =
+            GameServer::new("test", Limit::default(), RoomSettings::default());
+        server.attach_audit(Arc::new(AuditLog::open(&path).unwrap()));
+        let server_task = tokio::spawn(async move { server.run().await });
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let (_, _rx_2) = connect_listening_user("Player2", &handle).await;
+        handle
+            .vote(&conn_id_1, &Vote::Option("3".into()))
+            .await
+            .unwrap();
+        handle.reveal(&conn_id_1).await.unwrap();
+        handle.set_locked(&conn_id_1, true).await.unwrap();
+        handle.kick(&conn_id_1, "Player2").await.unwrap();
+        handle.disconnect(&conn_id_1).await.unwrap();
+
+        shutdown_test_server(&handle, server_task).await;
+
+        let entries: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(path).unwrap();
+
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {} {}",
+                    entry["event"].as_str().unwrap(),
+                    entry["nickname"].as_str().unwrap_or("-"),
+                    entry["detail"].as_str().unwrap_or("-")
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "join Player1 -",
+                "join Player2 -",
+                "vote Player1 3",
+                "reveal Player1 -",
+                "lock Player1 -",
+                "kick Player1 Player2",
+                "leave Player1 -",
+            ]
+        );
+        assert!(entries.iter().all(|entry| entry["room"] == "test"));
+    }
+
+    #[tokio::test]
+    async fn test_password_and_invites() {
+        let (_server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        let password = Some("correct horse".to_string());
+        assert!(matches!(
+            handle.set_password(&conn_id_2, &password).await,
+            Err(Error::NotFacilitator)
+        ));
+        handle.set_password(&conn_id_1, &password).await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::RoomProtected(true)
+        );
+
+        let join = |credentials: Credentials| {
+            let handle = handle.clone();
+            async move {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let nickname = format!("Player{}", uuid::Uuid::new_v4().simple());
+                let result = handle.connect(tx, &nickname, false, &credentials).await;
+                (result, rx)
+            }
+        };
+
+        let (result, _) = join(Credentials::default()).await;
+        assert!(matches!(result, Err(Error::PasswordRequired)));
+        let (result, _) = join(Credentials {
+            password: Some("wrong horse".into()),
+            invite: None,
+        })
+        .await;
+        assert!(matches!(result, Err(Error::WrongPassword)));
+        let (result, mut rx) = join(Credentials {
+            password: password.clone(),
+            invite: None,
+        })
+        .await;
+        assert!(result.is_ok());
+        assert!(matches!(rx.try_recv(), Ok(OutboundMessage::ResumeToken(_))));
+        assert_eq!(rx.try_recv(), Ok(OutboundMessage::Deck(Deck::default())));
+        assert_eq!(rx.try_recv(), Ok(OutboundMessage::RoomProtected(true)));
+
+        assert!(matches!(
+            handle.create_invite(&conn_id_1, 0).await,
+            Err(Error::InvalidInviteLifetime(_))
+        ));
+        handle.create_invite(&conn_id_1, 30).await.unwrap();
+        let OutboundMessage::Invite { link, minutes } = last_message(&mut rx_1) else {
+            panic!("no invite received");
+        };
+        assert_eq!(minutes, 30);
+        let invite = link.strip_prefix("/r/test?invite=").unwrap().to_string();
+
+        let (result, _rx_invited) = join(Credentials {
+            password: None,
+            invite: Some(invite),
+        })
+        .await;
+        assert!(result.is_ok());
+        let (result, _) = join(Credentials {
+            password: password.clone(),
+            invite: Some("123.abc".into()),
+        })
+        .await;
+        assert!(matches!(result, Err(Error::InvalidInvite)));
+
+        handle.set_password(&conn_id_1, &None).await.unwrap();
+        let (result, _) = join(Credentials::default()).await;
+        assert!(result.is_ok());
+
+        shutdown_test_server(&handle, server_task).await;
+    }
+
+    #[tokio::test]
+    async fn test_facilitator_handover() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, mut rx_2) = connect_listening_user("Player2", &handle).await;
+        let token = facilitator_token(&mut rx_1);
+
+        // leaving hands the role over with a new token
+        handle.disconnect(&conn_id_1).await.unwrap();
+        let token_2 = facilitator_token(&mut rx_2);
+        assert_ne!(token_2, token);
+
+        // so the previous facilitator can't claim it back
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        assert!(matches!(
+            handle.claim(&conn_id_1, &token).await,
+            Err(Error::InvalidFacilitatorToken)
+        ));
+
+        // whoever holds the current token can, and gets a new one
+        handle.claim(&conn_id_1, &token_2).await.unwrap();
+        let token_1 = facilitator_token(&mut rx_1);
+        assert_ne!(token_1, token_2);
+        assert!(matches!(
+            handle.reveal(&conn_id_2).await,
+            Err(Error::NotFacilitator)
+        ));
+        assert!(matches!(
+            handle.claim(&conn_id_2, &token_2).await,
+            Err(Error::InvalidFacilitatorToken)
+        ));
+
+        // a transfer rotates the token too
+        handle.promote(&conn_id_1, "Player2").await.unwrap();
+        assert_ne!(facilitator_token(&mut rx_2), token_1);
+        assert!(matches!(
+            handle.claim(&conn_id_1, &token_1).await,
+            Err(Error::InvalidFacilitatorToken)
+        ));
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.facilitator, Some(conn_id_2));
+        assert_eq!(
+            server.users_summary(),
+            OutboundMessage::UserList(vec![
+                Participant {
+                    nickname: "Player1".into(),
+                    facilitator: false,
+                    observer: false,
+                },
+                Participant {
+                    nickname: "Player2".into(),
+                    facilitator: true,
+                    observer: false,
+                },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_observer() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let (tx, mut rx_2) = mpsc::unbounded_channel();
+        let conn_id_2 = handle
+            .connect(tx, "Player2", true, &Credentials::default())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            vote(&conn_id_2, Vote::from(3), &handle).await,
+            Err(Error::ObserverCannotVote)
+        ));
+
+        // the observer doesn't hold back the reveal, but still sees the result
+        vote(&conn_id_1, Vote::from(3), &handle).await.unwrap();
+        assert_eq!(
+            result_message(&mut rx_2),
+            OutboundMessage::VotesResult {
+                votes: vec![("Player1".into(), Vote::from(3))],
+                story: None
+            }
+        );
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(
+            server.users_summary(),
+            OutboundMessage::UserList(vec![
+                Participant {
+                    nickname: "Player1".into(),
+                    facilitator: true,
+                    observer: false,
+                },
+                Participant {
+                    nickname: "Player2".into(),
+                    facilitator: false,
+                    observer: true,
+                },
             ])
         );
     }
+
+    #[tokio::test]
+    async fn test_stories() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        let story = Story::new("PROJ-1", "Login page");
+        assert!(matches!(
+            handle.add_story(&conn_id_2, &story).await,
+            Err(Error::NotFacilitator)
+        ));
+        handle.add_story(&conn_id_1, &story).await.unwrap();
+        handle
+            .add_story(&conn_id_1, &Story::new("PROJ-2", "Sign up"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            handle.add_story(&conn_id_1, &story).await,
+            Err(Error::InvalidStory(_))
+        ));
+        handle
+            .edit_story(
+                &conn_id_1,
+                "PROJ-2",
+                &None,
+                &Some("https://example.com/PROJ-2".into()),
+                &None,
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            handle.accept(&conn_id_1, &Vote::from(5)).await,
+            Err(Error::RoundNotRevealed)
+        ));
+        vote(&conn_id_1, Vote::from(5), &handle).await.unwrap();
+        vote(&conn_id_2, Vote::from(8), &handle).await.unwrap();
+        assert_eq!(
+            result_message(&mut rx_1),
+            OutboundMessage::VotesResult {
+                votes: vec![
+                    ("Player1".into(), Vote::from(5)),
+                    ("Player2".into(), Vote::from(8)),
+                ],
+                story: Some("PROJ-1".into()),
+            }
+        );
+
+        // accepting records the estimate and moves on to the next story
+        handle.accept(&conn_id_1, &Vote::from(8)).await.unwrap();
+        handle.move_story(&conn_id_1, "PROJ-2", 1).await.unwrap();
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.round, Round::Voting);
+        assert_eq!(
+            server.stories_summary(),
+            OutboundMessage::Stories {
+                stories: vec![
+                    Story {
+                        link: Some("https://example.com/PROJ-2".into()),
+                        ..Story::new("PROJ-2", "Sign up")
+                    },
+                    Story {
+                        estimate: Some(Vote::from(8)),
+                        ..story
+                    },
+                ],
+                current: Some("PROJ-2".into()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let story = Story::new("PROJ-1", "Login page");
+        handle.add_story(&conn_id_1, &story).await.unwrap();
+
+        // cleared rounds are discarded, revealed ones are kept
+        vote(&conn_id_1, Vote::from(3), &handle).await.unwrap();
+        handle.clear_votes(&conn_id_1).await.unwrap();
+        vote(&conn_id_1, Vote::from(5), &handle).await.unwrap();
+        handle.next_round(&conn_id_1).await.unwrap();
+        vote(&conn_id_1, Vote::from(8), &handle).await.unwrap();
+        handle.accept(&conn_id_1, &Vote::from(8)).await.unwrap();
+        vote(&conn_id_1, Vote::from(2), &handle).await.unwrap();
+
+        let history = handle.history(None).await.unwrap();
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].story, Some(story.clone()));
+        assert_eq!(history[0].votes, vec![("Player1".into(), Vote::from(5))]);
+        assert_eq!(history[0].estimate, None);
+        assert_eq!(history[1].votes, vec![("Player1".into(), Vote::from(8))]);
+        assert_eq!(history[1].estimate, Some(Vote::from(8)));
+        assert_eq!(server.lock().await.history, history);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timer() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, mut rx_2) = connect_listening_user("Player2", &handle).await;
+
+        assert!(matches!(
+            handle.start_timer(&conn_id_2, 3, true).await,
+            Err(Error::NotFacilitator)
+        ));
+        assert!(matches!(
+            handle
+                .start_timer(&conn_id_1, MAX_TIMER_SECONDS + 1, true)
+                .await,
+            Err(Error::InvalidTimer(MAX_TIMER_SECONDS))
+        ));
+
+        vote(&conn_id_1, Vote::from(3), &handle).await.unwrap();
+        handle.start_timer(&conn_id_1, 3, true).await.unwrap();
+        assert_eq!(
+            last_message(&mut rx_2),
+            OutboundMessage::TimerStarted {
+                seconds: 3,
+                reveal: true
+            }
+        );
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(last_message(&mut rx_2), OutboundMessage::TimerTick(2));
+
+        // the timer reveals the votes when it runs out
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx_2.try_recv() {
+            messages.push(message);
+        }
+        assert_eq!(
+            messages[..2],
+            [OutboundMessage::TimerTick(1), OutboundMessage::TimerExpired]
+        );
+        assert!(matches!(messages[2], OutboundMessage::VotesResult { .. }));
+
+        handle.next_round(&conn_id_1).await.unwrap();
+        handle.start_timer(&conn_id_1, 60, false).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10_500)).await;
+
+        // late joiners catch up with the countdown
+        let (_, mut rx_3) = connect_listening_user("Player3", &handle).await;
+        let mut messages = Vec::new();
+        while let Ok(message) = rx_3.try_recv() {
+            messages.push(message);
+        }
+        assert!(messages.contains(&OutboundMessage::TimerTick(50)));
+
+        handle.stop_timer(&conn_id_1).await.unwrap();
+        assert!(matches!(
+            handle.stop_timer(&conn_id_1).await,
+            Err(Error::NoTimerRunning)
+        ));
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(last_message(&mut rx_2), OutboundMessage::TimerStopped);
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert!(server.timer.is_none());
+        assert_eq!(server.round, Round::Voting);
+    }
+
+    fn resume_token(rx: &mut mpsc::UnboundedReceiver<OutboundMessage>) -> String {
+        match rx.try_recv() {
+            Ok(OutboundMessage::ResumeToken(token)) => token,
+            message => panic!("expected a resume token, got {:?}", message),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resume() {
+        let (server, handle, server_task) = setup_test_server();
+
+        let (_conn_id_1, mut rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, mut rx_2) = connect_listening_user("Player2", &handle).await;
+        let token = resume_token(&mut rx_2);
+
+        vote(&conn_id_2, Vote::from(5), &handle).await.unwrap();
+        handle.suspend(&conn_id_2).await.unwrap();
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert!(matches!(
+            handle.resume(tx, "wrong").await,
+            Err(Error::InvalidResumeToken)
+        ));
+
+        // the vote survives the reconnection
+        let (tx, mut rx_3) = mpsc::unbounded_channel();
+        let (conn_id_3, nickname) = handle.resume(tx, &token).await.unwrap();
+        assert_eq!(nickname, "Player2");
+        assert_ne!(conn_id_3, conn_id_2);
+        let mut messages = Vec::new();
+        while let Ok(message) = rx_3.try_recv() {
+            messages.push(message);
+        }
+        assert_eq!(messages[0], OutboundMessage::Deck(Deck::default()));
+        assert!(messages.contains(&OutboundMessage::YourVote(Vote::from(5))));
+
+        // the previous session is gone, so it can't drop the resumed one
+        handle.suspend(&conn_id_2).await.unwrap();
+        handle.disconnect(&conn_id_2).await.unwrap();
+
+        // without coming back, the user leaves when the grace period ends
+        handle.suspend(&conn_id_3).await.unwrap();
+        tokio::time::sleep(RoomSettings::default().resume_grace + Duration::from_secs(1)).await;
+        assert_eq!(
+            last_message(&mut rx_1),
+            OutboundMessage::UserList(vec![Participant {
+                nickname: "Player1".into(),
+                facilitator: true,
+                observer: false,
+            }])
+        );
+
+        // unlock the server
+        shutdown_test_server(&handle, server_task).await;
+        let server = server.lock().await;
+
+        assert_eq!(server.users.len(), 1);
+        assert!(server.keep_alive.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_kick_suspended_user() {
+        let (_server, handle, server_task) = setup_test_server();
+
+        let (conn_id_1, _rx_1) = connect_listening_user("Player1", &handle).await;
+        let (conn_id_2, _rx_2) = connect_listening_user("Player2", &handle).await;
+
+        handle.suspend(&conn_id_2).await.unwrap();
+        handle.kick(&conn_id_1, "Player2").await.unwrap();
+
+        // nothing keeps the room running once its last handle is gone
+        drop(handle);
+        tokio::time::timeout(Duration::from_secs(60), server_task)
+            .await
+            .expect("the room outlived its handles")
+            .expect("Server task did not complete");
+    }
 }
@@ -1,12 +1,18 @@
 use super::{
-    game::{ConnId, OutboundMessage, Vote},
+    game::{
+        ConnId, Credentials, Deck, Nickname, OutboundMessage, RoomInfo, RoundInfo, RoundRecord,
+        Story, Vote,
+    },
     GameServer,
 };
 use crate::error::Result;
 use shared::UserStatus;
-use tokio::sync::{
-    mpsc::{self},
-    oneshot,
+use tokio::{
+    sync::{
+        mpsc::{self},
+        oneshot,
+    },
+    time,
 };
 
 #[derive(Debug)]
@@ -14,6 +20,8 @@ pub enum Command {
     Connect {
         conn_tx: mpsc::UnboundedSender<OutboundMessage>,
         nickname: String,
+        observer: bool,
+        credentials: Credentials,
         res_tx: Option<oneshot::Sender<Result<ConnId>>>,
     },
 
@@ -22,6 +30,17 @@ pub enum Command {
         res_tx: Option<oneshot::Sender<Result<()>>>,
     },
 
+    Suspend {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Resume {
+        conn_tx: mpsc::UnboundedSender<OutboundMessage>,
+        token: String,
+        res_tx: Option<oneshot::Sender<Result<(ConnId, Nickname)>>>,
+    },
+
     Vote {
         conn_id: ConnId,
         vote: Vote,
@@ -34,8 +53,192 @@ pub enum Command {
         res_tx: Option<oneshot::Sender<Result<()>>>,
     },
 
-    #[cfg(test)]
-    Shutdown,
+    SetNickname {
+        conn_id: ConnId,
+        nickname: String,
+        res_tx: Option<oneshot::Sender<Result<Nickname>>>,
+    },
+
+    Who {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Status {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    SetDeck {
+        conn_id: ConnId,
+        deck: Deck,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Reveal {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    NextRound {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    SetAutoReveal {
+        conn_id: ConnId,
+        auto_reveal: bool,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Claim {
+        conn_id: ConnId,
+        token: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Promote {
+        conn_id: ConnId,
+        nickname: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Kick {
+        conn_id: ConnId,
+        nickname: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    ForceAway {
+        conn_id: ConnId,
+        nickname: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    ClearVotes {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    SetLocked {
+        conn_id: ConnId,
+        locked: bool,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    SetPassword {
+        conn_id: ConnId,
+        password: Option<String>,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    CreateInvite {
+        conn_id: ConnId,
+        minutes: u64,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    AddStory {
+        conn_id: ConnId,
+        story: Story,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    EditStory {
+        conn_id: ConnId,
+        key: String,
+        title: Option<String>,
+        link: Option<String>,
+        description: Option<String>,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    MoveStory {
+        conn_id: ConnId,
+        key: String,
+        position: usize,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    RemoveStory {
+        conn_id: ConnId,
+        key: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    SelectStory {
+        conn_id: ConnId,
+        key: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    ListStories {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Accept {
+        conn_id: ConnId,
+        estimate: Vote,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    /// Without credentials for the users of the room and the operators.
+    History {
+        credentials: Option<Credentials>,
+        res_tx: Option<oneshot::Sender<Result<Vec<RoundRecord>>>>,
+    },
+
+    Ping {
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    StartTimer {
+        conn_id: ConnId,
+        seconds: u64,
+        reveal: bool,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    StopTimer {
+        conn_id: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Shutdown {
+        message: Option<String>,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Inspect {
+        res_tx: Option<oneshot::Sender<Result<RoomInfo>>>,
+    },
+
+    CurrentRound {
+        res_tx: Option<oneshot::Sender<Result<RoundInfo>>>,
+    },
+
+    Remove {
+        target: ConnId,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Close {
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+
+    Notice {
+        message: String,
+        res_tx: Option<oneshot::Sender<Result<()>>>,
+    },
+}
+
+/// A command along with the moment it was sent, to measure how long it
+/// waited for the game server.
+#[derive(Debug)]
+pub struct Queued {
+    pub command: Command,
+    pub at: time::Instant,
 }
 
 impl GameServer {
@@ -44,9 +247,13 @@ impl GameServer {
             Command::Connect {
                 conn_tx,
                 nickname,
+                observer,
+                credentials,
                 res_tx,
             } => {
-                let result = self.connect(conn_tx, &nickname).await;
+                let result = self
+                    .connect(conn_tx, &nickname, observer, &credentials)
+                    .await;
                 if let Some(res_tx) = res_tx {
                     let _ = res_tx.send(result);
                 }
@@ -59,6 +266,24 @@ impl GameServer {
                 }
             }
 
+            Command::Suspend { conn_id, res_tx } => {
+                let result = self.suspend(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Resume {
+                conn_tx,
+                token,
+                res_tx,
+            } => {
+                let result = self.resume(conn_tx, &token);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
             Command::Vote {
                 conn_id,
                 vote,
@@ -80,76 +305,830 @@ impl GameServer {
                     let _ = res_tx.send(result);
                 }
             }
-            #[cfg(test)]
-            Command::Shutdown => {}
-        }
-    }
 
-    pub async fn run(&mut self) {
-        while let Some(cmd) = self.cmd_rx.recv().await {
-            match cmd {
-                #[cfg(test)]
-                Command::Shutdown => {
-                    println!("Shutting down server.");
-                    break;
+            Command::SetNickname {
+                conn_id,
+                nickname,
+                res_tx,
+            } => {
+                let result = self.set_nickname(&conn_id, &nickname);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
                 }
-                _ => self.process_command(cmd).await,
             }
-        }
-    }
-}
 
-#[derive(Debug, Clone)]
-pub struct GameHandle {
-    pub cmd_tx: mpsc::UnboundedSender<Command>,
-}
+            Command::Who { conn_id, res_tx } => {
+                let result = self.who(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-impl GameHandle {
-    pub async fn connect(
-        &self,
-        conn_tx: mpsc::UnboundedSender<OutboundMessage>,
-        nickname: &str,
-    ) -> Result<ConnId> {
-        let (res_tx, res_rx) = oneshot::channel();
+            Command::Status { conn_id, res_tx } => {
+                let result = self.status(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        self.cmd_tx.send(Command::Connect {
-            conn_tx,
-            nickname: nickname.into(),
-            res_tx: Some(res_tx),
-        })?;
+            Command::SetDeck {
+                conn_id,
+                deck,
+                res_tx,
+            } => {
+                let result = self.set_deck(&conn_id, &deck);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        res_rx.await?
-    }
+            Command::Reveal { conn_id, res_tx } => {
+                let result = self.reveal(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-    pub async fn disconnect(&self, conn_id: &ConnId) -> Result<()> {
-        let (res_tx, res_rx) = oneshot::channel();
+            Command::NextRound { conn_id, res_tx } => {
+                let result = self.next_round(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        self.cmd_tx.send(Command::Disconnect {
-            conn_id: conn_id.clone(),
-            res_tx: Some(res_tx),
-        })?;
+            Command::SetAutoReveal {
+                conn_id,
+                auto_reveal,
+                res_tx,
+            } => {
+                let result = self.set_auto_reveal(&conn_id, auto_reveal);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        res_rx.await?
-    }
+            Command::Claim {
+                conn_id,
+                token,
+                res_tx,
+            } => {
+                let result = self.claim(&conn_id, &token);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-    pub async fn vote(&self, conn_id: &ConnId, vote: &Vote) -> Result<()> {
-        let (res_tx, res_rx) = oneshot::channel();
+            Command::Promote {
+                conn_id,
+                nickname,
+                res_tx,
+            } => {
+                let result = self.promote(&conn_id, &nickname);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        self.cmd_tx.send(Command::Vote {
-            conn_id: conn_id.clone(),
-            vote: vote.clone(),
-            res_tx: Some(res_tx),
-        })?;
+            Command::Kick {
+                conn_id,
+                nickname,
+                res_tx,
+            } => {
+                let result = self.kick(&conn_id, &nickname);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        res_rx.await?
-    }
+            Command::ForceAway {
+                conn_id,
+                nickname,
+                res_tx,
+            } => {
+                let result = self.force_away(&conn_id, &nickname);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-    pub async fn set_status(&self, conn_id: &ConnId, status: &UserStatus) -> Result<()> {
-        let (res_tx, res_rx) = oneshot::channel();
+            Command::ClearVotes { conn_id, res_tx } => {
+                let result = self.clear_votes(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
 
-        self.cmd_tx.send(Command::SetAway {
-            conn_id: conn_id.clone(),
-            status: status.clone(),
+            Command::SetLocked {
+                conn_id,
+                locked,
+                res_tx,
+            } => {
+                let result = self.set_locked(&conn_id, locked);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+            Command::SetPassword {
+                conn_id,
+                password,
+                res_tx,
+            } => {
+                let result = self.set_password(&conn_id, &password);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::CreateInvite {
+                conn_id,
+                minutes,
+                res_tx,
+            } => {
+                let result = self.create_invite(&conn_id, minutes);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::AddStory {
+                conn_id,
+                story,
+                res_tx,
+            } => {
+                let result = self.add_story(&conn_id, &story);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::EditStory {
+                conn_id,
+                key,
+                title,
+                link,
+                description,
+                res_tx,
+            } => {
+                let result = self.edit_story(&conn_id, &key, &title, &link, &description);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::MoveStory {
+                conn_id,
+                key,
+                position,
+                res_tx,
+            } => {
+                let result = self.move_story(&conn_id, &key, position);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::RemoveStory {
+                conn_id,
+                key,
+                res_tx,
+            } => {
+                let result = self.remove_story(&conn_id, &key);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::SelectStory {
+                conn_id,
+                key,
+                res_tx,
+            } => {
+                let result = self.select_story(&conn_id, &key);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::ListStories { conn_id, res_tx } => {
+                let result = self.list_stories(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Accept {
+                conn_id,
+                estimate,
+                res_tx,
+            } => {
+                let result = self.accept(&conn_id, &estimate);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::History {
+                credentials,
+                res_tx,
+            } => {
+                let result = match &credentials {
+                    Some(credentials) => self.authenticate(credentials).map(|()| self.history()),
+                    None => Ok(self.history()),
+                };
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Ping { res_tx } => {
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(Ok(()));
+                }
+            }
+
+            Command::StartTimer {
+                conn_id,
+                seconds,
+                reveal,
+                res_tx,
+            } => {
+                let result = self.start_timer(&conn_id, seconds, reveal);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::StopTimer { conn_id, res_tx } => {
+                let result = self.stop_timer(&conn_id);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Shutdown { message, res_tx } => {
+                let result = self.shutdown(message);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Inspect { res_tx } => {
+                let result = Ok(self.info());
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::CurrentRound { res_tx } => {
+                let result = Ok(self.round_info());
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Remove { target, res_tx } => {
+                let result = self.remove(&target);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Close { res_tx } => {
+                let result = self.close();
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+
+            Command::Notice { message, res_tx } => {
+                let result = self.notice(&message);
+                if let Some(res_tx) = res_tx {
+                    let _ = res_tx.send(result);
+                }
+            }
+        }
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            let timer_tick = self.next_timer_tick();
+            let suspension_expiry = self.next_suspension_expiry();
+
+            tokio::select! {
+                queued = self.cmd_rx.recv() => match queued {
+                    Some(Queued { command, at }) => {
+                        let shutdown =
+                            matches!(command, Command::Shutdown { .. } | Command::Close { .. });
+
+                        self.report_queued(at);
+                        self.process_command(command).await;
+                        self.persist();
+
+                        if shutdown {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                _ = time::sleep_until(timer_tick.unwrap_or_else(time::Instant::now)),
+                    if timer_tick.is_some() =>
+                {
+                    if let Err(err) = self.tick_timer() {
+                        log::error!("Failed to update the timer: {}", err);
+                    }
+                }
+                _ = time::sleep_until(suspension_expiry.unwrap_or_else(time::Instant::now)),
+                    if suspension_expiry.is_some() =>
+                {
+                    if let Err(err) = self.expire_suspended() {
+                        log::error!("Failed to remove suspended users: {}", err);
+                    }
+                }
+            }
+
+            self.report_users();
+        }
+
+        self.flush().await;
+        self.report_finished();
+        log::debug!("Game finished");
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameHandle {
+    pub cmd_tx: mpsc::UnboundedSender<Queued>,
+}
+
+impl GameHandle {
+    pub fn send(&self, command: Command) -> Result<()> {
+        self.cmd_tx
+            .send(Queued {
+                command,
+                at: time::Instant::now(),
+            })
+            .map_err(|mpsc::error::SendError(queued)| mpsc::error::SendError(queued.command))?;
+
+        Ok(())
+    }
+
+    pub async fn connect(
+        &self,
+        conn_tx: mpsc::UnboundedSender<OutboundMessage>,
+        nickname: &str,
+        observer: bool,
+        credentials: &Credentials,
+    ) -> Result<ConnId> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Connect {
+            conn_tx,
+            nickname: nickname.into(),
+            observer,
+            credentials: credentials.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn disconnect(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Disconnect {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn suspend(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Suspend {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn resume(
+        &self,
+        conn_tx: mpsc::UnboundedSender<OutboundMessage>,
+        token: &str,
+    ) -> Result<(ConnId, Nickname)> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Resume {
+            conn_tx,
+            token: token.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn vote(&self, conn_id: &ConnId, vote: &Vote) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Vote {
+            conn_id: conn_id.clone(),
+            vote: vote.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_status(&self, conn_id: &ConnId, status: &UserStatus) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetAway {
+            conn_id: conn_id.clone(),
+            status: status.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_nickname(&self, conn_id: &ConnId, nickname: &str) -> Result<Nickname> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetNickname {
+            conn_id: conn_id.clone(),
+            nickname: nickname.to_string(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn who(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Who {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn status(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Status {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_deck(&self, conn_id: &ConnId, deck: &Deck) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetDeck {
+            conn_id: conn_id.clone(),
+            deck: deck.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn reveal(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Reveal {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn next_round(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::NextRound {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_auto_reveal(&self, conn_id: &ConnId, auto_reveal: bool) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetAutoReveal {
+            conn_id: conn_id.clone(),
+            auto_reveal,
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn claim(&self, conn_id: &ConnId, token: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Claim {
+            conn_id: conn_id.clone(),
+            token: token.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn promote(&self, conn_id: &ConnId, nickname: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Promote {
+            conn_id: conn_id.clone(),
+            nickname: nickname.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn kick(&self, conn_id: &ConnId, nickname: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Kick {
+            conn_id: conn_id.clone(),
+            nickname: nickname.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn force_away(&self, conn_id: &ConnId, nickname: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::ForceAway {
+            conn_id: conn_id.clone(),
+            nickname: nickname.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn clear_votes(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::ClearVotes {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn start_timer(&self, conn_id: &ConnId, seconds: u64, reveal: bool) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::StartTimer {
+            conn_id: conn_id.clone(),
+            seconds,
+            reveal,
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn stop_timer(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::StopTimer {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_locked(&self, conn_id: &ConnId, locked: bool) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetLocked {
+            conn_id: conn_id.clone(),
+            locked,
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn set_password(&self, conn_id: &ConnId, password: &Option<String>) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SetPassword {
+            conn_id: conn_id.clone(),
+            password: password.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn create_invite(&self, conn_id: &ConnId, minutes: u64) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::CreateInvite {
+            conn_id: conn_id.clone(),
+            minutes,
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn add_story(&self, conn_id: &ConnId, story: &Story) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::AddStory {
+            conn_id: conn_id.clone(),
+            story: story.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn edit_story(
+        &self,
+        conn_id: &ConnId,
+        key: &str,
+        title: &Option<String>,
+        link: &Option<String>,
+        description: &Option<String>,
+    ) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::EditStory {
+            conn_id: conn_id.clone(),
+            key: key.into(),
+            title: title.clone(),
+            link: link.clone(),
+            description: description.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn move_story(&self, conn_id: &ConnId, key: &str, position: usize) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::MoveStory {
+            conn_id: conn_id.clone(),
+            key: key.into(),
+            position,
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn remove_story(&self, conn_id: &ConnId, key: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::RemoveStory {
+            conn_id: conn_id.clone(),
+            key: key.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn select_story(&self, conn_id: &ConnId, key: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::SelectStory {
+            conn_id: conn_id.clone(),
+            key: key.into(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn list_stories(&self, conn_id: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::ListStories {
+            conn_id: conn_id.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn accept(&self, conn_id: &ConnId, estimate: &Vote) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Accept {
+            conn_id: conn_id.clone(),
+            estimate: estimate.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn history(&self, credentials: Option<&Credentials>) -> Result<Vec<RoundRecord>> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::History {
+            credentials: credentials.cloned(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    /// Tells everyone in the room that the server is going away, then stops
+    /// the game server once its state is saved.
+    pub async fn shutdown(&self, message: &Option<String>) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Shutdown {
+            message: message.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn inspect(&self) -> Result<RoomInfo> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Inspect {
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn current_round(&self) -> Result<RoundInfo> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::CurrentRound {
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn remove(&self, target: &ConnId) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Remove {
+            target: target.clone(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    /// Sends everyone away and stops the game server.
+    pub async fn close(&self) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Close {
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    pub async fn notice(&self, message: &str) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Notice {
+            message: message.to_string(),
+            res_tx: Some(res_tx),
+        })?;
+
+        res_rx.await?
+    }
+
+    /// Resolves once the game server went through its queue up to this point.
+    pub async fn ping(&self) -> Result<()> {
+        let (res_tx, res_rx) = oneshot::channel();
+
+        self.send(Command::Ping {
             res_tx: Some(res_tx),
         })?;
 
@@ -178,10 +1157,10 @@ mod tests {
             let expected_conn_id = expected_conn_id.clone();
             async move {
                 if let Some(Command::Connect {
-                    conn_tx: _,
                     nickname: n,
                     res_tx: r,
-                }) = cmd_rx.recv().await
+                    ..
+                }) = cmd_rx.recv().await.map(|queued| queued.command)
                 {
                     assert_eq!(n, nickname);
                     r.unwrap().send(Ok(expected_conn_id)).unwrap();
@@ -190,7 +1169,7 @@ mod tests {
         });
 
         let conn_id = game_handle
-            .connect(conn_tx, nickname.as_str())
+            .connect(conn_tx, nickname.as_str(), false, &Credentials::default())
             .await
             .expect("Failed to receive ConnId");
 
@@ -203,17 +1182,17 @@ mod tests {
         let game_handle = GameHandle { cmd_tx };
 
         let conn_id = ConnId::new();
-        let vote_value = Vote::new(2);
+        let vote_value = Vote::from(2);
 
         tokio::spawn({
             let conn_id = conn_id.clone();
             async move {
                 if let Some(Command::Vote {
                     conn_id: id, vote, ..
-                }) = cmd_rx.recv().await
+                }) = cmd_rx.recv().await.map(|queued| queued.command)
                 {
                     assert_eq!(id, conn_id);
-                    assert_eq!(vote, Vote::Option(2));
+                    assert_eq!(vote, Vote::from(2));
                 }
             }
         });
@@ -0,0 +1,414 @@
+use super::{Credentials, GameHandle, GameServer, Queued, RoomSettings};
+use crate::{
+    audit::AuditLog,
+    auth,
+    error::{Error, Result},
+    limit::Limit,
+    metrics::Metrics,
+    storage::Storage,
+};
+use futures_util::future::join_all;
+use shared::RoundRecord;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc::WeakUnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+pub type RoomId = String;
+
+pub const DEFAULT_ROOM: &str = "default";
+
+fn validate_room_id(room_id: &str) -> Result<&str> {
+    let room_id = room_id.trim();
+
+    let is_valid = !room_id.is_empty()
+        && room_id.len() <= 32
+        && room_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !is_valid {
+        log::error!("Invalid room name: {}", room_id);
+        return Err(Error::InvalidRoomName(room_id.into()));
+    }
+
+    Ok(room_id)
+}
+
+/// Registry of the running rooms, each one served by its own `GameServer`.
+///
+/// Only weak references to the game servers are kept here, so a room shuts
+/// down by itself as soon as the last session holding its handle goes away.
+#[derive(Clone)]
+pub struct Rooms {
+    rooms: Arc<Mutex<HashMap<RoomId, WeakUnboundedSender<Queued>>>>,
+    limit: Limit,
+    settings: RoomSettings,
+    storage: Option<Arc<dyn Storage>>,
+    metrics: Arc<Metrics>,
+    audit: Option<Arc<AuditLog>>,
+    closing: CancellationToken,
+}
+
+impl Rooms {
+    pub fn new(limit: Limit) -> Self {
+        Self {
+            rooms: Arc::new(Mutex::new(HashMap::new())),
+            limit,
+            settings: RoomSettings::default(),
+            storage: None,
+            metrics: Arc::new(Metrics::default()),
+            audit: None,
+            closing: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_settings(self, settings: RoomSettings) -> Self {
+        Self { settings, ..self }
+    }
+
+    /// Restores the rooms from `storage`, and saves them back to it.
+    pub fn with_storage(self, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage: Some(storage),
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> Self {
+        Self { metrics, ..self }
+    }
+
+    pub fn with_audit(self, audit: Arc<AuditLog>) -> Self {
+        Self {
+            audit: Some(audit),
+            ..self
+        }
+    }
+
+    /// Returns the handle of a running room, without creating it.
+    pub fn get(&self, room_id: &str) -> Option<GameHandle> {
+        let rooms = self.rooms.lock().unwrap_or_else(|err| err.into_inner());
+
+        rooms
+            .get(room_id.trim())
+            .and_then(|cmd_tx| cmd_tx.upgrade())
+            .map(|cmd_tx| GameHandle { cmd_tx })
+    }
+
+    /// Takes a room off the registry and sends everyone in it away.
+    pub async fn close(&self, room_id: &str) -> Result<bool> {
+        let game_handle = {
+            let mut rooms = self.rooms.lock().unwrap_or_else(|err| err.into_inner());
+            rooms
+                .remove(room_id.trim())
+                .and_then(|cmd_tx| cmd_tx.upgrade())
+                .map(|cmd_tx| GameHandle { cmd_tx })
+        };
+
+        match game_handle {
+            Some(game_handle) => game_handle.close().await.map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Handles of all the running rooms.
+    pub fn running(&self) -> Vec<(RoomId, GameHandle)> {
+        let rooms = self.rooms.lock().unwrap_or_else(|err| err.into_inner());
+
+        rooms
+            .iter()
+            .filter_map(|(room_id, cmd_tx)| {
+                let cmd_tx = cmd_tx.upgrade()?;
+                Some((room_id.clone(), GameHandle { cmd_tx }))
+            })
+            .collect()
+    }
+
+    /// The history of a room, whether it is running or only stored. Protected
+    /// rooms check the `credentials`, unless the operators are asking.
+    pub async fn history(
+        &self,
+        room_id: &str,
+        credentials: Option<&Credentials>,
+    ) -> Result<Option<Vec<RoundRecord>>> {
+        if let Some(game_handle) = self.get(room_id) {
+            return game_handle.history(credentials).await.map(Some);
+        }
+
+        let Some(storage) = &self.storage else {
+            return Ok(None);
+        };
+        let room_id = validate_room_id(room_id)?;
+        let Some(state) = storage.load(room_id)? else {
+            return Ok(None);
+        };
+
+        if let Some(credentials) = credentials {
+            auth::authenticate(
+                room_id,
+                state.password.as_deref(),
+                &self.settings.invites,
+                credentials,
+            )?;
+        }
+        Ok(Some(state.history))
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing.is_cancelled()
+    }
+
+    /// Resolves once the server starts shutting down.
+    pub async fn closed(&self) {
+        self.closing.cancelled().await
+    }
+
+    /// Stops every running room, after which no room can be created anymore.
+    pub async fn shutdown(&self, message: &Option<String>) {
+        self.closing.cancel();
+
+        let running = self.running();
+        let results = join_all(
+            running
+                .iter()
+                .map(|(_, game_handle)| game_handle.shutdown(message)),
+        )
+        .await;
+
+        for ((room_id, _), result) in running.iter().zip(results) {
+            if let Err(err) = result {
+                log::error!("Failed to shut room {} down: {}", room_id, err);
+            }
+        }
+    }
+
+    pub fn acquire(&self, room_id: &str) -> Result<GameHandle> {
+        if self.is_closing() {
+            return Err(Error::ShuttingDown);
+        }
+
+        let room_id = validate_room_id(room_id)?;
+        if let Some(game_handle) = self.get(room_id) {
+            return Ok(game_handle);
+        }
+
+        // read the disk before locking, so a slow one doesn't hold up every room
+        let state = match &self.storage {
+            Some(storage) => storage.load(room_id)?,
+            None => None,
+        };
+
+        let mut rooms = self.rooms.lock().unwrap_or_else(|err| err.into_inner());
+
+        rooms.retain(|_, cmd_tx| cmd_tx.strong_count() > 0);
+
+        // someone else may have started the room in the meantime
+        if let Some(cmd_tx) = rooms.get(room_id).and_then(|cmd_tx| cmd_tx.upgrade()) {
+            return Ok(GameHandle { cmd_tx });
+        }
+
+        let (mut game_server, game_handle) =
+            GameServer::new(room_id, Limit::new(self.limit.max), self.settings.clone());
+        if let Some(storage) = &self.storage {
+            game_server.attach_storage(room_id, storage.clone(), state);
+        }
+        game_server.attach_metrics(room_id, self.metrics.clone());
+        if let Some(audit) = &self.audit {
+            game_server.attach_audit(audit.clone());
+        }
+        tokio::spawn(async move { game_server.run().await });
+
+        log::debug!("Room created: {}", room_id);
+        rooms.insert(room_id.to_owned(), game_handle.cmd_tx.downgrade());
+
+        Ok(game_handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{Credentials, Deck, OutboundMessage, Vote},
+        storage::{MemoryStorage, RoomState},
+    };
+    use std::time::Duration;
+
+    /// Rooms are saved in the background, so wait for the state to show up.
+    async fn wait_saved(
+        storage: &MemoryStorage,
+        room_id: &str,
+        until: impl Fn(&RoomState) -> bool,
+    ) -> RoomState {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match storage.load(room_id).unwrap() {
+                    Some(state) if until(&state) => return state,
+                    _ => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+        })
+        .await
+        .expect("the room was never saved")
+    }
+
+    #[tokio::test]
+    async fn test_acquire_same_room() {
+        let rooms = Rooms::new(Limit::default());
+
+        let handle_a = rooms.acquire("team-a").unwrap();
+        let handle_b = rooms.acquire("team-a").unwrap();
+        let handle_c = rooms.acquire("team-b").unwrap();
+
+        assert!(handle_a.cmd_tx.same_channel(&handle_b.cmd_tx));
+        assert!(!handle_a.cmd_tx.same_channel(&handle_c.cmd_tx));
+    }
+
+    #[tokio::test]
+    async fn test_room_is_recreated_after_release() {
+        let rooms = Rooms::new(Limit::default());
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let weak = handle.cmd_tx.downgrade();
+        drop(handle);
+
+        let handle = rooms.acquire("team-a").unwrap();
+        assert!(weak.upgrade().is_none());
+        assert_eq!(handle.cmd_tx.strong_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_room_name() {
+        let rooms = Rooms::new(Limit::default());
+
+        assert!(matches!(rooms.acquire(""), Err(Error::InvalidRoomName(_))));
+        assert!(matches!(
+            rooms.acquire("team a"),
+            Err(Error::InvalidRoomName(_))
+        ));
+        assert!(rooms.acquire(" team-a ").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_room_is_restored_from_storage() {
+        let storage = Arc::new(MemoryStorage::default());
+        let rooms = Rooms::new(Limit::default()).with_storage(storage.clone());
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let conn_id = handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        handle.set_deck(&conn_id, &Deck::t_shirt()).await.unwrap();
+        handle.vote(&conn_id, &Vote::from("XL")).await.unwrap();
+        handle.next_round(&conn_id).await.unwrap();
+        handle.disconnect(&conn_id).await.unwrap();
+        drop(handle);
+
+        let state = wait_saved(&storage, "team-a", |state| !state.history.is_empty()).await;
+        assert_eq!(state.deck, Deck::t_shirt());
+        assert_eq!(state.history.len(), 1);
+
+        // nobody is in the room, but its history is still there
+        assert_eq!(
+            rooms.history("team-a", None).await.unwrap(),
+            Some(state.history)
+        );
+        assert_eq!(rooms.history("team-b", None).await.unwrap(), None);
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            rx.recv().await,
+            Some(OutboundMessage::ResumeToken(_))
+        ));
+        assert_eq!(
+            rx.recv().await,
+            Some(OutboundMessage::Deck(Deck::t_shirt()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rooms_report_metrics() {
+        let metrics = Arc::new(Metrics::default());
+        let rooms = Rooms::new(Limit::default()).with_metrics(metrics.clone());
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let conn_id = handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        handle.vote(&conn_id, &Vote::from(3)).await.unwrap();
+        handle.next_round(&conn_id).await.unwrap();
+
+        let out = metrics.render(&Limit::default());
+        assert!(out.contains("omnipoker_room_users{room=\"team-a\"} 1\n"));
+        assert!(out.contains("omnipoker_votes_total 1\n"));
+        assert!(out.contains("omnipoker_rounds_completed_total 1\n"));
+        assert!(out.contains("omnipoker_command_queue_seconds_count 3\n"));
+
+        handle.disconnect(&conn_id).await.unwrap();
+        drop(handle);
+        tokio::task::yield_now().await;
+        assert!(!metrics.render(&Limit::default()).contains("team-a"));
+    }
+
+    #[tokio::test]
+    async fn test_votes_are_not_saved() {
+        let storage = Arc::new(MemoryStorage::default());
+        let rooms = Rooms::new(Limit::default()).with_storage(storage.clone());
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let conn_id = handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        handle.vote(&conn_id, &Vote::from(3)).await.unwrap();
+
+        // the room is gone once it stops answering, with its writes done
+        rooms.shutdown(&None).await;
+        assert!(handle.ping().await.is_err());
+        assert_eq!(storage.load("team-a").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let storage = Arc::new(MemoryStorage::default());
+        let rooms = Rooms::new(Limit::default()).with_storage(storage.clone());
+
+        let handle = rooms.acquire("team-a").unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let conn_id = handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        handle.set_deck(&conn_id, &Deck::t_shirt()).await.unwrap();
+
+        let message = Some("back in 5 minutes".to_string());
+        rooms.shutdown(&message).await;
+
+        let mut last = None;
+        while let Ok(message) = rx.try_recv() {
+            last = Some(message);
+        }
+        assert_eq!(last, Some(OutboundMessage::ShuttingDown(message)));
+        assert!(handle.ping().await.is_err());
+        assert_eq!(
+            storage.load("team-a").unwrap().unwrap().deck,
+            Deck::t_shirt()
+        );
+
+        assert!(rooms.is_closing());
+        assert!(matches!(rooms.acquire("team-b"), Err(Error::ShuttingDown)));
+    }
+}
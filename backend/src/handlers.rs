@@ -1,16 +1,32 @@
 use crate::{
-    game::GameHandle,
-    limit::{release_session, try_acquire_session, Limit},
+    admin,
+    config::Config,
+    error::Error,
+    export,
+    game::{Credentials, Rooms},
+    limit::{try_acquire_session, AddrLimit, Limit, Peer},
+    metrics::Metrics,
     session,
 };
 use actix_web::{get, web, HttpResponse, Responder};
 use actix_web::{web::Payload, HttpRequest};
+use futures_util::future::join_all;
 use rust_embed::RustEmbed;
 use serde::Deserialize;
-use std::sync::{Arc, Mutex};
-use tokio::task::spawn_local;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{task::spawn_local, time::timeout};
+
+/// How long a room may take to answer the readiness probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Carries the password of a protected room when downloading its history.
+const PASSWORD_HEADER: &str = "X-Room-Password";
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
     Json,
@@ -19,41 +35,212 @@ pub enum Mode {
 
 #[derive(Deserialize)]
 pub struct QueryParams {
-    mode: Option<Mode>,
+    pub mode: Option<Mode>,
+    pub room: Option<String>,
+    pub invite: Option<String>,
+}
+
+/// The address of the client, as told by the proxy in front of the server
+/// when it is trusted.
+fn client_addr(req: &HttpRequest, trust_proxy: bool) -> Option<IpAddr> {
+    if !trust_proxy {
+        return req.peer_addr().map(|addr| addr.ip());
+    }
+
+    let info = req.connection_info();
+    let addr = info.realip_remote_addr()?;
+    addr.parse::<SocketAddr>()
+        .map(|addr| addr.ip())
+        .or_else(|_| addr.parse())
+        .ok()
 }
 
 #[get("/ws")]
 pub async fn ws(
     req: HttpRequest,
     stream: Payload,
-    game_handler: web::Data<GameHandle>,
+    rooms: web::Data<Rooms>,
     session_count: web::Data<Arc<Mutex<Limit>>>,
+    config: web::Data<Config>,
+    metrics: web::Data<Metrics>,
     query: web::Query<QueryParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let (res, session, msg_stream) = actix_ws::handle(&req, stream)?;
 
-    try_acquire_session(&session_count)?;
+    let peer = Peer {
+        addr: client_addr(&req, config.trust_proxy),
+        limit: req
+            .app_data::<web::Data<AddrLimit>>()
+            .expect("the address limit is registered with the app")
+            .clone()
+            .into_inner(),
+    };
+
+    // an address over its cap is turned away without taking a session slot
+    let Some(peer_slot) = peer.try_acquire() else {
+        spawn_local(session::reject(
+            session,
+            Error::TooManyConnections,
+            query.into_inner().mode,
+        ));
+        return Ok(res);
+    };
+
+    let session_slot = try_acquire_session(&session_count, &metrics)?;
 
-    let session_count = session_count.clone();
     spawn_local(async move {
+        // released however the session ends
+        let _slots = (session_slot, peer_slot);
         session::init(
-            (*game_handler.get_ref()).clone(),
+            rooms.get_ref().clone(),
             session,
             msg_stream,
-            query.mode.clone(),
+            query.into_inner(),
+            config.into_inner(),
+            metrics.into_inner(),
         )
         .await;
-
-        release_session(&session_count);
     });
 
     Ok(res)
 }
 
+#[get("/healthz")]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().body("ok")
+}
+
+/// Ready while new sessions are accepted and every running room answers.
+#[get("/readyz")]
+pub async fn readyz(
+    rooms: web::Data<Rooms>,
+    session_count: web::Data<Arc<Mutex<Limit>>>,
+) -> HttpResponse {
+    if rooms.is_closing() {
+        return HttpResponse::ServiceUnavailable().body(Error::ShuttingDown.to_string());
+    }
+
+    let full = session_count
+        .lock()
+        .map(|limit| limit.is_reached())
+        .unwrap_or(true);
+    if full {
+        return HttpResponse::ServiceUnavailable().body("Too many concurrent sessions");
+    }
+
+    let running = rooms.running();
+    let probes = running
+        .iter()
+        .map(|(_, game_handle)| timeout(PROBE_TIMEOUT, game_handle.ping()));
+
+    for ((room_id, _), result) in running.iter().zip(join_all(probes).await) {
+        if !matches!(result, Ok(Ok(()))) {
+            log::error!("Room {} is not responding", room_id);
+            return HttpResponse::ServiceUnavailable()
+                .body(format!("Room {} is not responding", room_id));
+        }
+    }
+
+    HttpResponse::Ok().body("ready")
+}
+
+#[get("/metrics")]
+pub async fn prometheus(
+    session_count: web::Data<Arc<Mutex<Limit>>>,
+    metrics: web::Data<Metrics>,
+) -> HttpResponse {
+    let sessions = session_count
+        .lock()
+        .map(|limit| limit.clone())
+        .unwrap_or_else(|err| err.into_inner().clone());
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics.render(&sessions))
+}
+
+#[derive(Deserialize)]
+pub struct HistoryParams {
+    pub invite: Option<String>,
+}
+
+/// Protected rooms want their password in the `X-Room-Password` header, an
+/// invite in the query string, or the admin token.
+#[get("/rooms/{room}/history.{format}")]
+pub async fn history(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<HistoryParams>,
+    rooms: web::Data<Rooms>,
+    config: web::Data<Config>,
+) -> HttpResponse {
+    let (room, format) = path.into_inner();
+    let credentials = Credentials {
+        password: req
+            .headers()
+            .get(PASSWORD_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        invite: query.into_inner().invite,
+    };
+    let credentials = (!admin::is_admin(&req, &config)).then_some(credentials);
+
+    let records = match rooms.history(&room, credentials.as_ref()).await {
+        Ok(Some(records)) => records,
+        Ok(None) | Err(Error::InvalidRoomName(_)) => {
+            return HttpResponse::NotFound().body("404 - Room not found");
+        }
+        Err(err @ (Error::PasswordRequired | Error::WrongPassword | Error::InvalidInvite)) => {
+            log::warn!("Unauthorized history request for {}: {}", room, err);
+            return HttpResponse::Unauthorized().body(err.to_string());
+        }
+        Err(err) => {
+            log::error!("failed to read the history of {}: {}", room, err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let disposition = (
+        "Content-Disposition",
+        format!("attachment; filename=\"{}-history.{}\"", room, format),
+    );
+
+    match format.as_str() {
+        "csv" => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(disposition)
+            .body(export::to_csv(&records)),
+        "json" => HttpResponse::Ok().insert_header(disposition).json(records),
+        "md" => HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .insert_header(disposition)
+            .body(export::to_markdown(&room, &records)),
+        _ => HttpResponse::NotFound().body("404 - Not Found"),
+    }
+}
+
 #[derive(RustEmbed)]
 #[folder = "$CARGO_MANIFEST_DIR/../frontend/dist"]
 struct Assets;
 
+fn embedded(filename: &str) -> HttpResponse {
+    if let Some(content) = Assets::get(filename) {
+        let body = content.data;
+        let mime_type = mime_guess::from_path(filename).first_or_text_plain();
+        HttpResponse::Ok()
+            .content_type(mime_type.as_ref())
+            .body(body)
+    } else {
+        HttpResponse::NotFound().body("404 - Not Found")
+    }
+}
+
+/// Invite links open the web page, which picks the room and invite up.
+#[get("/r/{room}")]
+pub async fn invite_page() -> impl Responder {
+    embedded("index.html")
+}
+
 #[get("/{filename:.*}")]
 pub async fn assets(filename: web::Path<String>) -> impl Responder {
     let filename = if filename == web::Path::from("".to_owned()) {
@@ -61,13 +248,180 @@ pub async fn assets(filename: web::Path<String>) -> impl Responder {
     } else {
         &*filename
     };
-    if let Some(content) = Assets::get(&filename) {
-        let body = content.data;
-        let mime_type = mime_guess::from_path(&*filename).first_or_text_plain();
-        HttpResponse::Ok()
-            .content_type(mime_type.as_ref())
-            .body(body)
-    } else {
-        HttpResponse::NotFound().body("404 - Not Found")
+    embedded(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth::Invites, game::RoomSettings};
+    use actix_web::{test, App};
+    use tokio::sync::mpsc;
+
+    #[actix_web::test]
+    async fn test_client_addr() {
+        let req = test::TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "203.0.113.7, 10.0.0.1"))
+            .to_http_request();
+        assert_eq!(client_addr(&req, false), "10.0.0.1".parse().ok());
+        assert_eq!(client_addr(&req, true), "203.0.113.7".parse().ok());
+
+        let req = test::TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .to_http_request();
+        assert_eq!(client_addr(&req, true), "10.0.0.1".parse().ok());
+    }
+
+    #[actix_web::test]
+    async fn test_history() {
+        let rooms = Rooms::new(Limit::default());
+        let _game_handle = rooms.acquire("team-a").unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(rooms.clone()))
+                .app_data(web::Data::new(Config::default()))
+                .service(history),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/rooms/team-a/history.csv")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+        assert_eq!(
+            test::read_body(res).await,
+            "round,timestamp,story,votes,estimate\n"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rooms/team-a/history.json")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(test::read_body(res).await, "[]");
+
+        for uri in ["/rooms/team-b/history.csv", "/rooms/team-a/history.xml"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), actix_web::http::StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_protected_history() {
+        let invites = Invites::new(b"secret");
+        let rooms = Rooms::new(Limit::default()).with_settings(RoomSettings {
+            invites: invites.clone(),
+            ..RoomSettings::default()
+        });
+        let game_handle = rooms.acquire("team-a").unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let conn_id = game_handle
+            .connect(tx, "Player1", false, &Credentials::default())
+            .await
+            .unwrap();
+        game_handle
+            .set_password(&conn_id, &Some("hunter2".to_string()))
+            .await
+            .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(rooms.clone()))
+                .app_data(web::Data::new(Config {
+                    admin_token: Some("admin".to_string()),
+                    ..Config::default()
+                }))
+                .service(history),
+        )
+        .await;
+
+        let uri = "/rooms/team-a/history.json";
+        for req in [
+            test::TestRequest::get().uri(uri),
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header((PASSWORD_HEADER, "wrong")),
+            test::TestRequest::get()
+                .uri(&format!("{}?invite=123.abc", uri))
+                .insert_header(("Authorization", "Bearer nope")),
+        ] {
+            let res = test::call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        }
+
+        let invite = invites.create("team-a", Duration::from_secs(60));
+        for req in [
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header((PASSWORD_HEADER, "hunter2")),
+            test::TestRequest::get().uri(&format!("{}?invite={}", uri, invite)),
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", "Bearer admin")),
+        ] {
+            let res = test::call_service(&app, req.to_request()).await;
+            assert!(res.status().is_success());
+            assert_eq!(test::read_body(res).await, "[]");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_prometheus() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.vote_cast();
+        let mut limit = Limit::new(10);
+        limit.increment();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(Mutex::new(limit))))
+                .app_data(web::Data::from(metrics))
+                .service(prometheus),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("omnipoker_sessions 1\n"));
+        assert!(body.contains("omnipoker_votes_total 1\n"));
+    }
+
+    #[actix_web::test]
+    async fn test_health() {
+        let rooms = Rooms::new(Limit::default());
+        let _game_handle = rooms.acquire("team-a").unwrap();
+        let session_count = Arc::new(Mutex::new(Limit::new(1)));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(rooms.clone()))
+                .app_data(web::Data::new(session_count.clone()))
+                .service(healthz)
+                .service(readyz),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+        assert_eq!(test::read_body(res).await, "ready");
+
+        session_count.lock().unwrap().increment();
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            res.status(),
+            actix_web::http::StatusCode::SERVICE_UNAVAILABLE
+        );
     }
 }
@@ -1,10 +1,13 @@
+use crate::metrics::Metrics;
 use actix_web::web;
 use std::{
-    ffi::OsString,
+    collections::HashMap,
+    net::IpAddr,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Limit {
     pub count: usize,
     pub max: usize,
@@ -30,18 +33,6 @@ impl Limit {
     }
 }
 
-impl From<OsString> for Limit {
-    fn from(s: OsString) -> Self {
-        match s.into_string() {
-            Ok(s) => match s.parse() {
-                Ok(max) => Self::new(max),
-                Err(_) => Self::default(),
-            },
-            Err(_) => Self::default(),
-        }
-    }
-}
-
 impl Default for Limit {
     fn default() -> Self {
         Self::new(15)
@@ -54,9 +45,23 @@ impl std::fmt::Display for Limit {
     }
 }
 
+/// Holds a session slot, given back when dropped so that even a session
+/// that panics frees it.
+#[derive(Debug)]
+pub struct SessionSlot {
+    session_count: web::Data<Arc<Mutex<Limit>>>,
+}
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        release_session(&self.session_count);
+    }
+}
+
 pub fn try_acquire_session(
     session_count: &web::Data<Arc<Mutex<Limit>>>,
-) -> Result<(), actix_web::Error> {
+    metrics: &Metrics,
+) -> Result<SessionSlot, actix_web::Error> {
     let mut session_count_guard = session_count.lock().map_err(|_| {
         log::error!("Failed to acquire session count lock");
         actix_web::error::ErrorInternalServerError("Failed to acquire session count lock")
@@ -64,6 +69,7 @@ pub fn try_acquire_session(
 
     if session_count_guard.is_reached() {
         log::warn!("Too many concurrent sessions; rejecting new session");
+        metrics.session_rejected();
         return Err(actix_web::error::ErrorTooManyRequests(
             "Too many concurrent sessions",
         ));
@@ -71,10 +77,12 @@ pub fn try_acquire_session(
 
     session_count_guard.increment();
     log::debug!("Session started. Active sessions: {}", session_count_guard);
-    Ok(())
+    Ok(SessionSlot {
+        session_count: session_count.clone(),
+    })
 }
 
-pub fn release_session(session_count: &web::Data<Arc<Mutex<Limit>>>) {
+fn release_session(session_count: &web::Data<Arc<Mutex<Limit>>>) {
     if let Ok(mut session_count_guard) = session_count.lock() {
         session_count_guard.decrement();
         log::debug!("Session ended. Active sessions: {}", session_count_guard);
@@ -82,3 +90,187 @@ pub fn release_session(session_count: &web::Data<Arc<Mutex<Limit>>>) {
         log::error!("Failed to acquire session count lock for decrement");
     }
 }
+
+/// Waits for every session to end, checking every `poll`.
+pub async fn wait_for_sessions(session_count: &Mutex<Limit>, poll: Duration) {
+    loop {
+        let count = session_count
+            .lock()
+            .map(|limit| limit.count)
+            .unwrap_or_default();
+        if count == 0 {
+            return;
+        }
+
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Caps the sessions opened from the same address.
+#[derive(Debug)]
+pub struct AddrLimit {
+    max: usize,
+    counts: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl AddrLimit {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn try_acquire(&self, addr: IpAddr) -> bool {
+        let mut counts = self.counts.lock().unwrap_or_else(|err| err.into_inner());
+        let count = counts.entry(addr).or_default();
+        if *count >= self.max {
+            log::warn!("Too many sessions from {}; rejecting new session", addr);
+            return false;
+        }
+
+        *count += 1;
+        true
+    }
+
+    pub fn release(&self, addr: IpAddr) {
+        let mut counts = self.counts.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(count) = counts.get_mut(&addr) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&addr);
+            }
+        }
+    }
+}
+
+/// The address a session comes from, counted against its `AddrLimit`.
+#[derive(Clone, Debug)]
+pub struct Peer {
+    pub addr: Option<IpAddr>,
+    pub limit: Arc<AddrLimit>,
+}
+
+impl Peer {
+    /// The slot is given back when the returned guard is dropped.
+    pub fn try_acquire(self) -> Option<PeerSlot> {
+        self.addr
+            .is_none_or(|addr| self.limit.try_acquire(addr))
+            .then_some(PeerSlot { peer: self })
+    }
+}
+
+#[derive(Debug)]
+pub struct PeerSlot {
+    peer: Peer,
+}
+
+impl Drop for PeerSlot {
+    fn drop(&mut self) {
+        if let Some(addr) = self.peer.addr {
+            self.peer.limit.release(addr);
+        }
+    }
+}
+
+/// A token bucket holding up to `burst` messages, refilled by `rate` every second.
+#[derive(Debug)]
+pub struct RateLimit {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimit {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            burst: burst as f64,
+            tokens: burst as f64,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn try_take(&mut self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    fn try_take_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addr_limit() {
+        let limit = AddrLimit::new(2);
+        let (a, b) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+
+        assert!(limit.try_acquire(a));
+        assert!(limit.try_acquire(a));
+        assert!(!limit.try_acquire(a));
+        assert!(limit.try_acquire(b));
+
+        limit.release(a);
+        assert!(limit.try_acquire(a));
+    }
+
+    #[test]
+    fn test_session_slot() {
+        let session_count = web::Data::new(Arc::new(Mutex::new(Limit::new(1))));
+        let metrics = Metrics::default();
+
+        let slot = try_acquire_session(&session_count, &metrics).unwrap();
+        assert!(try_acquire_session(&session_count, &metrics).is_err());
+
+        drop(slot);
+        assert_eq!(session_count.lock().unwrap().count, 0);
+    }
+
+    #[test]
+    fn test_peer_slot() {
+        let peer = Peer {
+            addr: Some("10.0.0.1".parse().unwrap()),
+            limit: Arc::new(AddrLimit::new(1)),
+        };
+
+        let slot = peer.clone().try_acquire();
+        assert!(slot.is_some());
+        assert!(peer.clone().try_acquire().is_none());
+
+        drop(slot);
+        assert!(peer.try_acquire().is_some());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut limit = RateLimit::new(2, 3);
+        let start = limit.last;
+
+        assert!((0..3).all(|_| limit.try_take_at(start)));
+        assert!(!limit.try_take_at(start));
+
+        // half a second brings back one message
+        let later = start + Duration::from_millis(500);
+        assert!(limit.try_take_at(later));
+        assert!(!limit.try_take_at(later));
+
+        // and the bucket never holds more than the burst
+        let much_later = later + Duration::from_secs(60);
+        assert!((0..3).all(|_| limit.try_take_at(much_later)));
+        assert!(!limit.try_take_at(much_later));
+    }
+}
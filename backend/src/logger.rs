@@ -1,26 +1,59 @@
-use chrono::Local;
+use chrono::{Local, SecondsFormat, Utc};
 use clap::ValueEnum;
 use colored::*;
 use env_logger::{Builder, Env, Target};
+use log::kv::{Key, Value, VisitSource};
+use serde::Deserialize;
+use serde_json::{json, Map};
 use std::io::Write;
 
 use crate::limit;
 
-#[derive(ValueEnum, Clone)]
+#[derive(ValueEnum, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     Error,
     Warn,
+    #[default]
     Info,
     Debug,
     Trace,
 }
 
-impl Default for LogLevel {
-    fn default() -> Self {
-        LogLevel::Info
+#[derive(ValueEnum, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Collects the `key = value` pairs of a log record, e.g. the room and nickname.
+struct Fields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.insert(key.to_string(), json!(value.to_string()));
+        Ok(())
     }
 }
 
+fn json_line(record: &log::Record) -> String {
+    let mut fields = Fields(Map::new());
+    let _ = record.key_values().visit(&mut fields);
+
+    let mut line = fields.0;
+    line.insert(
+        "time".into(),
+        json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    line.insert("level".into(), json!(record.level().as_str()));
+    line.insert("target".into(), json!(record.target()));
+    line.insert("message".into(), json!(record.args().to_string()));
+
+    serde_json::Value::Object(line).to_string()
+}
+
 impl From<LogLevel> for log::Level {
     fn from(log_level: LogLevel) -> Self {
         match log_level {
@@ -33,14 +66,26 @@ impl From<LogLevel> for log::Level {
     }
 }
 
-pub fn welcome(addr: &str, limit: &limit::Limit) {
-    let addr_web = format!("http://{}/", addr);
-    let addr_ws = format!("http://{}/ws", addr);
-    let addrs = format!(
+pub fn welcome(addr: &str, limit: &limit::Limit, tls: bool, http_redirect: Option<&str>) {
+    let (http, ws) = if tls {
+        ("https", "wss")
+    } else {
+        ("http", "ws")
+    };
+    let addr_web = format!("{}://{}/", http, addr);
+    let addr_ws = format!("{}://{}/ws", ws, addr);
+    let mut addrs = format!(
         "\n\t{} directly on a browser\n\t{} to connect a websocket",
         addr_web.blue(),
         addr_ws.blue()
     );
+    if let Some(redirect) = http_redirect {
+        let addr_redirect = format!("http://{}/", redirect);
+        addrs.push_str(&format!(
+            "\n\t{} redirecting to HTTPS",
+            addr_redirect.blue()
+        ));
+    }
 
     log::info!(
         "Starting service: \"planning-poker\",\n\nlimit of sessions: {},\nlistening on:{}\n",
@@ -49,14 +94,21 @@ pub fn welcome(addr: &str, limit: &limit::Limit) {
     );
 }
 
-pub fn init(log_level: &LogLevel) {
+pub fn init(log_level: &LogLevel, log_format: &LogFormat) {
     let log_level: log::Level = log_level.clone().into();
 
-    Builder::from_env(Env::default().default_filter_or(log_level.to_string()))
+    let mut builder = Builder::from_env(Env::default().default_filter_or(log_level.to_string()));
+    builder
         .target(Target::Stdout)
         .filter_module("actix_server", log::LevelFilter::Warn)
-        .filter_module("actix_web", log::LevelFilter::Warn)
-        .format(|buf, record| {
+        .filter_module("actix_web", log::LevelFilter::Warn);
+
+    if let LogFormat::Json = log_format {
+        // no escape codes in the middle of the messages
+        colored::control::set_override(false);
+        builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+    } else {
+        builder.format(|buf, record| {
             let level = match record.level() {
                 log::Level::Error => "ERROR".red().bold(),
                 log::Level::Warn => "WARN".yellow().bold(),
@@ -67,6 +119,39 @@ pub fn init(log_level: &LogLevel) {
 
             let timestamp = format!("[{}]", Local::now().format("%Y-%m-%d %H:%M:%S")).white();
             writeln!(buf, "{} {} {}", timestamp, level, record.args())
-        })
-        .init();
+        });
+    }
+
+    builder.init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line() {
+        let room = "team-a";
+        let args = format_args!("User joined: {}", "Alice");
+        let fields: &[(&str, Value)] = &[
+            ("room", Value::from(room)),
+            ("nickname", Value::from("Alice")),
+            ("event", Value::from("join")),
+        ];
+        let record = log::Record::builder()
+            .args(args)
+            .level(log::Level::Info)
+            .target("omnipoker::game")
+            .key_values(&fields)
+            .build();
+
+        let line: serde_json::Value = serde_json::from_str(&json_line(&record)).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "omnipoker::game");
+        assert_eq!(line["message"], "User joined: Alice");
+        assert_eq!(line["room"], "team-a");
+        assert_eq!(line["nickname"], "Alice");
+        assert_eq!(line["event"], "join");
+        assert!(line["time"].is_string());
+    }
 }
@@ -1,39 +1,183 @@
-use actix_web::{web::Data, App, HttpServer};
-use std::sync::{Arc, Mutex};
+use actix_web::{web, web::Data, App, HttpServer};
+use limit::{AddrLimit, Limit};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use storage::Storage;
 
+mod admin;
+mod audit;
+mod auth;
 mod cli;
+mod config;
 mod error;
+mod export;
 mod game;
 mod handlers;
 mod limit;
 mod logger;
+mod metrics;
 mod session;
+mod storage;
+mod tls;
+
+/// How often the shutdown checks whether every session is gone.
+const DRAIN_POLL: Duration = Duration::from_millis(100);
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to listen to SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> std::io::Result<()> {
-    let (addr, limit, log_level) = cli::get_args();
+    let config = match cli::get_config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    let tls_config = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => match tls::load_config(cert, key) {
+            Ok(tls_config) => Some(tls_config),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+        },
+        _ => None,
+    };
+    let limit = Limit::new(config.limit);
+
+    logger::init(&config.log, &config.log_format);
+    logger::welcome(
+        &config.addr,
+        &limit,
+        config.tls(),
+        config.http_redirect.as_deref(),
+    );
 
-    logger::init(&log_level);
-    logger::welcome(&addr, &limit);
+    let metrics = Arc::new(metrics::Metrics::default());
+    let rooms = game::Rooms::new(Limit::new(config.room_limit))
+        .with_settings(config.room_settings())
+        .with_metrics(metrics.clone());
+    let rooms = match &config.audit_log {
+        Some(path) => {
+            let audit = audit::AuditLog::open(path).map_err(|err| {
+                std::io::Error::other(format!("can't open {}: {}", path.display(), err))
+            })?;
+            log::info!("Writing the audit log to {}", path.display());
 
-    let (mut game_server, game_handler) = game::GameServer::new();
-    let server_task = tokio::spawn(async move { game_server.run().await });
+            rooms.with_audit(Arc::new(audit))
+        }
+        None => rooms,
+    };
+    let rooms = match &config.data_dir {
+        Some(dir) => {
+            let storage = storage::FileStorage::new(dir)
+                .map_err(|err| std::io::Error::other(err.to_string()))?;
+            let stored = storage
+                .rooms()
+                .map_err(|err| std::io::Error::other(err.to_string()))?;
+            log::info!(
+                "Storing rooms in {} ({} found)",
+                dir.display(),
+                stored.len()
+            );
+
+            rooms.with_storage(Arc::new(storage))
+        }
+        None => rooms,
+    };
     let session_count = Arc::new(Mutex::new(limit));
+    let addr_limit = Arc::new(AddrLimit::new(config.max_connections_per_ip));
+    let addr = config.addr.clone();
+    let drain_timeout = config.drain_timeout();
+    let shutdown_message = config.shutdown_message.clone();
+    let tls_port = config.port();
+    let http_redirect = config.http_redirect.clone();
+    let config = Data::new(config);
+
+    let server = HttpServer::new({
+        let rooms = rooms.clone();
+        let session_count = session_count.clone();
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(Data::new(game_handler.clone()))
-            .app_data(Data::new(session_count.clone()))
-            .service(handlers::ws)
-            .service(handlers::assets)
-    })
-    .bind(&addr)?
-    .run()
-    .await?;
-
-    if server_task.await.is_err() {
-        log::error!("Game server task failed");
+        move || {
+            App::new()
+                .app_data(Data::new(rooms.clone()))
+                .app_data(Data::new(session_count.clone()))
+                .app_data(config.clone())
+                .app_data(Data::from(metrics.clone()))
+                .app_data(Data::from(addr_limit.clone()))
+                .service(handlers::ws)
+                .service(handlers::healthz)
+                .service(handlers::readyz)
+                .service(handlers::prometheus)
+                .service(handlers::history)
+                .service(admin::scope())
+                .service(handlers::invite_page)
+                .service(handlers::assets)
+        }
+    });
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls_0_23(&addr, tls_config)?,
+        None => server.bind(&addr)?,
     }
+    .disable_signals()
+    .shutdown_timeout(drain_timeout.as_secs())
+    .run();
+
+    let redirect_handle = match http_redirect {
+        Some(redirect_addr) => {
+            let redirect = HttpServer::new(move || {
+                App::new().default_service(web::to(move |req| tls::redirect(req, tls_port)))
+            })
+            .workers(1)
+            .bind(&redirect_addr)?
+            .disable_signals()
+            .run();
+            let redirect_handle = redirect.handle();
+            tokio::spawn(redirect);
+
+            Some(redirect_handle)
+        }
+        None => None,
+    };
+
+    let server_handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        log::info!(
+            "Shutting down, waiting up to {:?} for the clients",
+            drain_timeout
+        );
+
+        let drain = async {
+            rooms.shutdown(&shutdown_message).await;
+            limit::wait_for_sessions(&session_count, DRAIN_POLL).await;
+        };
+        if tokio::time::timeout(drain_timeout, drain).await.is_err() {
+            log::warn!("Drain timeout reached; closing the remaining sessions");
+        }
+
+        if let Some(redirect_handle) = redirect_handle {
+            redirect_handle.stop(true).await;
+        }
+        server_handle.stop(true).await;
+    });
 
-    Ok(())
+    server.await
 }
@@ -0,0 +1,282 @@
+use crate::{game::RoomId, handlers::Mode, limit::Limit};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds, in seconds, of the command queue latency buckets.
+const LATENCY_BUCKETS: [f64; 9] = [0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+#[derive(Debug, Default)]
+struct ByMode {
+    json: AtomicU64,
+    text: AtomicU64,
+}
+
+impl ByMode {
+    fn inc(&self, mode: &Option<Mode>) {
+        match mode {
+            Some(Mode::Json) => self.json.fetch_add(1, Ordering::Relaxed),
+            _ => self.text.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Counters shared by the sessions and the game servers, rendered in the
+/// Prometheus text format by `/metrics`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    room_users: Mutex<BTreeMap<RoomId, usize>>,
+    votes: AtomicU64,
+    rounds: AtomicU64,
+    messages_in: ByMode,
+    messages_out: ByMode,
+    rejected_sessions: AtomicU64,
+    queue_latency: Histogram,
+}
+
+impl Metrics {
+    pub fn set_room_users(&self, room_id: &str, users: usize) {
+        let mut room_users = self
+            .room_users
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        room_users.insert(room_id.to_string(), users);
+    }
+
+    pub fn remove_room(&self, room_id: &str) {
+        let mut room_users = self
+            .room_users
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        room_users.remove(room_id);
+    }
+
+    pub fn vote_cast(&self) {
+        self.votes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn round_completed(&self) {
+        self.rounds.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn message_received(&self, mode: &Option<Mode>) {
+        self.messages_in.inc(mode);
+    }
+
+    pub fn message_sent(&self, mode: &Option<Mode>) {
+        self.messages_out.inc(mode);
+    }
+
+    pub fn session_rejected(&self) {
+        self.rejected_sessions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Time a command waited in a game server's queue before being processed.
+    pub fn command_queued(&self, duration: Duration) {
+        self.queue_latency.observe(duration);
+    }
+
+    pub fn render(&self, sessions: &Limit) -> String {
+        let mut out = String::new();
+        let counter = |value: &AtomicU64| value.load(Ordering::Relaxed);
+
+        header(&mut out, "sessions", "gauge", "Open WebSocket sessions.");
+        let _ = writeln!(out, "omnipoker_sessions {}", sessions.count);
+        header(
+            &mut out,
+            "sessions_max",
+            "gauge",
+            "Sessions allowed at the same time.",
+        );
+        let _ = writeln!(out, "omnipoker_sessions_max {}", sessions.max);
+
+        header(
+            &mut out,
+            "room_users",
+            "gauge",
+            "Users connected to a room.",
+        );
+        let room_users = self
+            .room_users
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        for (room_id, users) in room_users.iter() {
+            let _ = writeln!(
+                out,
+                "omnipoker_room_users{{room=\"{}\"}} {}",
+                room_id, users
+            );
+        }
+        drop(room_users);
+
+        header(&mut out, "votes_total", "counter", "Votes cast.");
+        let _ = writeln!(out, "omnipoker_votes_total {}", counter(&self.votes));
+
+        header(
+            &mut out,
+            "rounds_completed_total",
+            "counter",
+            "Revealed rounds moved to the history.",
+        );
+        let _ = writeln!(
+            out,
+            "omnipoker_rounds_completed_total {}",
+            counter(&self.rounds)
+        );
+
+        for (name, help, by_mode) in [
+            (
+                "messages_received_total",
+                "Messages received from clients.",
+                &self.messages_in,
+            ),
+            (
+                "messages_sent_total",
+                "Messages sent to clients.",
+                &self.messages_out,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(
+                out,
+                "omnipoker_{}{{mode=\"json\"}} {}",
+                name,
+                counter(&by_mode.json)
+            );
+            let _ = writeln!(
+                out,
+                "omnipoker_{}{{mode=\"text\"}} {}",
+                name,
+                counter(&by_mode.text)
+            );
+        }
+
+        header(
+            &mut out,
+            "rejected_sessions_total",
+            "counter",
+            "Connections refused because the session limit was reached.",
+        );
+        let _ = writeln!(
+            out,
+            "omnipoker_rejected_sessions_total {}",
+            counter(&self.rejected_sessions)
+        );
+
+        header(
+            &mut out,
+            "command_queue_seconds",
+            "histogram",
+            "Time commands wait for their game server.",
+        );
+        let mut cumulative = 0;
+        for (le, bucket) in LATENCY_BUCKETS.iter().zip(&self.queue_latency.buckets) {
+            cumulative += counter(bucket);
+            let _ = writeln!(
+                out,
+                "omnipoker_command_queue_seconds_bucket{{le=\"{}\"}} {}",
+                le, cumulative
+            );
+        }
+        let count = counter(&self.queue_latency.count);
+        let _ = writeln!(
+            out,
+            "omnipoker_command_queue_seconds_bucket{{le=\"+Inf\"}} {}",
+            count
+        );
+        let _ = writeln!(
+            out,
+            "omnipoker_command_queue_seconds_sum {}",
+            counter(&self.queue_latency.sum_micros) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "omnipoker_command_queue_seconds_count {}", count);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP omnipoker_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE omnipoker_{} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.set_room_users("team-a", 2);
+        metrics.set_room_users("team-b", 1);
+        metrics.remove_room("team-b");
+        metrics.vote_cast();
+        metrics.vote_cast();
+        metrics.round_completed();
+        metrics.message_received(&Some(Mode::Json));
+        metrics.message_sent(&None);
+        metrics.message_sent(&Some(Mode::Text));
+        metrics.session_rejected();
+        metrics.command_queued(Duration::from_micros(200));
+        metrics.command_queued(Duration::from_millis(20));
+        metrics.command_queued(Duration::from_secs(2));
+
+        let mut sessions = Limit::new(15);
+        sessions.increment();
+        let out = metrics.render(&sessions);
+
+        for line in [
+            "# TYPE omnipoker_sessions gauge",
+            "omnipoker_sessions 1",
+            "omnipoker_sessions_max 15",
+            "omnipoker_room_users{room=\"team-a\"} 2",
+            "omnipoker_votes_total 2",
+            "omnipoker_rounds_completed_total 1",
+            "omnipoker_messages_received_total{mode=\"json\"} 1",
+            "omnipoker_messages_received_total{mode=\"text\"} 0",
+            "omnipoker_messages_sent_total{mode=\"text\"} 2",
+            "omnipoker_rejected_sessions_total 1",
+            "# TYPE omnipoker_command_queue_seconds histogram",
+            "omnipoker_command_queue_seconds_bucket{le=\"0.0001\"} 0",
+            "omnipoker_command_queue_seconds_bucket{le=\"0.0005\"} 1",
+            "omnipoker_command_queue_seconds_bucket{le=\"0.05\"} 2",
+            "omnipoker_command_queue_seconds_bucket{le=\"1\"} 2",
+            "omnipoker_command_queue_seconds_bucket{le=\"+Inf\"} 3",
+            "omnipoker_command_queue_seconds_sum 2.0202",
+            "omnipoker_command_queue_seconds_count 3",
+        ] {
+            assert!(
+                out.lines().any(|l| l == line),
+                "missing {:?} in\n{}",
+                line,
+                out
+            );
+        }
+        assert!(!out.contains("team-b"));
+    }
+}
@@ -1,34 +1,106 @@
 use crate::{
+    config::Config,
     error::{Result, *},
-    game::{ConnId, GameHandle, Nickname, OutboundMessage},
-    handlers::Mode,
+    game::{ConnId, Credentials, GameHandle, Nickname, OutboundMessage, Rooms, DEFAULT_ROOM},
+    handlers::{Mode, QueryParams},
+    limit::RateLimit,
+    metrics::Metrics,
 };
-use actix_ws::{AggregatedMessage, CloseReason};
+use actix_ws::{AggregatedMessage, CloseCode, CloseReason};
 use futures_util::{
     future::{select, Either},
     StreamExt as _,
 };
-use shared::InboundMessage;
-use std::{
-    pin::pin,
-    time::{Duration, Instant},
+use shared::{
+    InboundMessage, Request, CLOSE_AUTH_FAILED, CLOSE_UNSUPPORTED_PROTOCOL, FEATURES,
+    PROTOCOL_VERSION,
 };
+use std::{future::pending, pin::pin, sync::Arc, time::Instant};
 use tokio::{sync::mpsc, time::interval};
 
 async fn handle_text_message(
     inbound: &InboundMessage,
     nickname: &mut Option<Nickname>,
     conn_id: &mut Option<ConnId>,
-    game_handler: &GameHandle,
+    game_handler: &mut Option<GameHandle>,
+    rooms: &Rooms,
+    query: &QueryParams,
     conn_tx: &mpsc::UnboundedSender<OutboundMessage>,
 ) -> Result<()> {
+    if let InboundMessage::Help = inbound {
+        conn_tx.send(OutboundMessage::Help)?;
+        return Ok(());
+    }
+
+    if let InboundMessage::Hello { version, features } = inbound {
+        if *version != PROTOCOL_VERSION {
+            return Err(Error::UnsupportedProtocol(*version));
+        }
+
+        log::debug!("client features: {}", features.join(", "));
+        conn_tx.send(OutboundMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+        })?;
+        return Ok(());
+    }
+
     if nickname.is_none() {
-        if let InboundMessage::Connect {
-            nickname: new_nickname,
-        } = inbound
-        {
-            *conn_id = Some(game_handler.connect(conn_tx.clone(), new_nickname).await?);
-            *nickname = Some(new_nickname.to_string());
+        match inbound {
+            InboundMessage::Connect {
+                nickname: new_nickname,
+                room: new_room,
+                observer,
+                password,
+                invite,
+            } => {
+                let room = new_room.as_ref().or(query.room.as_ref());
+                let handler = rooms.acquire(room.map_or(DEFAULT_ROOM, |room| room.as_str()))?;
+                let credentials = Credentials {
+                    password: password.clone(),
+                    invite: invite.clone().or_else(|| query.invite.clone()),
+                };
+
+                let new_conn_id = handler
+                    .connect(conn_tx.clone(), new_nickname, *observer, &credentials)
+                    .await?;
+
+                // text users get a reminder of the cards and the commands
+                if !matches!(query.mode, Some(Mode::Json)) {
+                    let info = handler.inspect().await?;
+                    let joined = info
+                        .users
+                        .iter()
+                        .find(|user| user.conn_id == new_conn_id.to_string())
+                        .map_or(new_nickname.trim(), |user| user.nickname.as_str());
+
+                    conn_tx.send(OutboundMessage::Joined {
+                        room: info.room.clone(),
+                        nickname: joined.to_string(),
+                        deck: info.deck.clone(),
+                    })?;
+                }
+
+                *conn_id = Some(new_conn_id);
+                *nickname = Some(new_nickname.to_string());
+                *game_handler = Some(handler);
+            }
+            InboundMessage::Resume {
+                token,
+                room: new_room,
+            } => {
+                let room = new_room.as_ref().or(query.room.as_ref());
+                let handler = rooms
+                    .get(room.map_or(DEFAULT_ROOM, |room| room.as_str()))
+                    .ok_or(Error::InvalidResumeToken)?;
+
+                let (new_conn_id, new_nickname) = handler.resume(conn_tx.clone(), token).await?;
+                *conn_id = Some(new_conn_id);
+                *nickname = Some(new_nickname);
+                *game_handler = Some(handler);
+            }
+            InboundMessage::Unknown => {}
+            _ => return Err(Error::NotJoined),
         }
 
         return Ok(());
@@ -36,10 +108,63 @@ async fn handle_text_message(
 
     // Commands after identifying
 
-    if let Some(conn_id) = conn_id {
+    if let (Some(conn_id), Some(game_handler)) = (conn_id, game_handler) {
         match inbound {
             InboundMessage::SetStatus(value) => game_handler.set_status(conn_id, value).await?,
+            InboundMessage::SetNickname(value) => {
+                *nickname = Some(game_handler.set_nickname(conn_id, value).await?)
+            }
+            InboundMessage::Who => game_handler.who(conn_id).await?,
+            InboundMessage::Status => game_handler.status(conn_id).await?,
             InboundMessage::Vote { value } => game_handler.vote(conn_id, value).await?,
+            InboundMessage::SetDeck(deck) => game_handler.set_deck(conn_id, deck).await?,
+            InboundMessage::Reveal => game_handler.reveal(conn_id).await?,
+            InboundMessage::NextRound => game_handler.next_round(conn_id).await?,
+            InboundMessage::SetAutoReveal(value) => {
+                game_handler.set_auto_reveal(conn_id, *value).await?
+            }
+            InboundMessage::Claim(token) => game_handler.claim(conn_id, token).await?,
+            InboundMessage::Promote(nickname) => game_handler.promote(conn_id, nickname).await?,
+            InboundMessage::Kick(nickname) => game_handler.kick(conn_id, nickname).await?,
+            InboundMessage::ForceAway(nickname) => {
+                game_handler.force_away(conn_id, nickname).await?
+            }
+            InboundMessage::ClearVotes => game_handler.clear_votes(conn_id).await?,
+            InboundMessage::SetLocked(value) => game_handler.set_locked(conn_id, *value).await?,
+            InboundMessage::SetPassword(password) => {
+                game_handler.set_password(conn_id, password).await?
+            }
+            InboundMessage::Invite(minutes) => {
+                game_handler.create_invite(conn_id, *minutes).await?
+            }
+            InboundMessage::AddStory(story) => game_handler.add_story(conn_id, story).await?,
+            InboundMessage::EditStory {
+                key,
+                title,
+                link,
+                description,
+            } => {
+                game_handler
+                    .edit_story(conn_id, key, title, link, description)
+                    .await?
+            }
+            InboundMessage::MoveStory { key, position } => {
+                game_handler.move_story(conn_id, key, *position).await?
+            }
+            InboundMessage::RemoveStory(key) => game_handler.remove_story(conn_id, key).await?,
+            InboundMessage::SelectStory(key) => game_handler.select_story(conn_id, key).await?,
+            InboundMessage::ListStories => game_handler.list_stories(conn_id).await?,
+            InboundMessage::Accept(estimate) => game_handler.accept(conn_id, estimate).await?,
+            InboundMessage::History => {
+                conn_tx.send(OutboundMessage::History(game_handler.history(None).await?))?
+            }
+            InboundMessage::StartTimer { seconds, reveal } => {
+                game_handler.start_timer(conn_id, *seconds, *reveal).await?
+            }
+            InboundMessage::StopTimer => game_handler.stop_timer(conn_id).await?,
+            InboundMessage::Connect { .. } | InboundMessage::Resume { .. } => {
+                return Err(Error::AlreadyJoined)
+            }
             _ => {}
         }
     }
@@ -47,66 +172,159 @@ async fn handle_text_message(
     Ok(())
 }
 
-async fn handle_error(result: Result<()>, session: &mut actix_ws::Session) -> Option<CloseReason> {
-    if let Err(err) = result {
-        log::error!("{}", err);
+async fn send_error(
+    err: &Error,
+    id: Option<u64>,
+    mode: &Option<Mode>,
+    session: &mut actix_ws::Session,
+) {
+    let message = match id {
+        Some(id) => OutboundMessage::Nack {
+            id,
+            code: err.code(),
+            message: err.to_string(),
+        },
+        None => OutboundMessage::Error(err.to_string()),
+    };
 
-        match err {
-            // handle errors that should close the connection
-            Error::NicknameAlreadyInUse(_) | Error::NicknameCannotBeEmpty => {
-                return Some(CloseReason {
-                    code: 1008.into(),
-                    description: Some(err.to_string()),
-                });
+    let text = match mode {
+        Some(Mode::Json) => {
+            serde_json::to_string(&message).expect("failed to serialize error message")
+        }
+        _ => message.to_string(),
+    };
+
+    // the client may already be gone
+    let _ = session.text(text).await;
+}
+
+/// Errors keeping a user from joining close the connection, but only
+/// until the user is in a room. Requests with an id get their `Nack`
+/// either way.
+async fn handle_error(
+    result: Result<()>,
+    id: Option<u64>,
+    joined: bool,
+    mode: &Option<Mode>,
+    session: &mut actix_ws::Session,
+) -> Option<CloseReason> {
+    let Err(err) = result else {
+        return None;
+    };
+    log::error!("{}", err);
+
+    let close_reason = match err {
+        // tell abusive clients why before closing the connection
+        Error::TooManyConnections | Error::TooManyMessages => {
+            send_error(&err, id, mode, session).await;
+            return Some(CloseReason {
+                code: 1008.into(),
+                description: Some(err.to_string()),
+            });
+        }
+        // handle errors that should close the connection
+        Error::NicknameAlreadyInUse(_)
+        | Error::NicknameCannotBeEmpty
+        | Error::InvalidRoomName(_)
+        | Error::RoomIsFull
+        | Error::RoomIsLocked
+        | Error::InvalidResumeToken
+            if !joined =>
+        {
+            CloseReason {
+                code: 1008.into(),
+                description: Some(err.to_string()),
             }
-            // handle errors that should be sent to the user
-            _ => {
-                session
-                    .text(
-                        serde_json::to_string(&OutboundMessage::Error(err.to_string()))
-                            .expect("failed to serialize error message"),
-                    )
-                    .await
-                    .expect("failed to send error message to the user");
+        }
+        Error::PasswordRequired | Error::WrongPassword | Error::InvalidInvite if !joined => {
+            CloseReason {
+                code: CLOSE_AUTH_FAILED.into(),
+                description: Some(err.to_string()),
             }
         }
-    }
+        Error::ShuttingDown => CloseReason {
+            code: CloseCode::Away,
+            description: Some(err.to_string()),
+        },
+        Error::UnsupportedProtocol(_) => CloseReason {
+            code: CLOSE_UNSUPPORTED_PROTOCOL.into(),
+            description: Some(err.to_string()),
+        },
+        // handle errors that should be sent to the user
+        _ => {
+            send_error(&err, id, mode, session).await;
+            return None;
+        }
+    };
 
-    None
+    if id.is_some() {
+        send_error(&err, id, mode, session).await;
+    }
+    Some(close_reason)
 }
 
-fn parse_inbound_message(text: &str, mode: &Option<Mode>) -> InboundMessage {
+/// JSON messages may carry the id of the request, and keep it even when
+/// the server can't make sense of the rest.
+fn parse_inbound_message(text: &str, mode: &Option<Mode>) -> Result<Request> {
     match mode {
-        Some(Mode::Json) => serde_json::from_str(text).unwrap_or(InboundMessage::Unknown),
-        _ => InboundMessage::from_string(text),
+        Some(Mode::Json) => Ok(serde_json::from_str(text).unwrap_or_else(|_| Request {
+            id: serde_json::from_str::<serde_json::Value>(text)
+                .ok()
+                .and_then(|value| value.get("id")?.as_u64()),
+            message: serde_json::from_str(text).unwrap_or(InboundMessage::Unknown),
+        })),
+        _ => InboundMessage::parse(text)
+            .map(|message| Request { id: None, message })
+            .map_err(Error::InvalidCommand),
     }
 }
 
-const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
-const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tells a client why it can't have a session, then closes the connection.
+pub async fn reject(mut session: actix_ws::Session, err: Error, mode: Option<Mode>) {
+    let close_reason = handle_error(Err(err), None, false, &mode, &mut session).await;
+    let _ = session.close(close_reason).await;
+}
 
 pub async fn init(
-    game_handler: GameHandle,
+    rooms: Rooms,
     mut session: actix_ws::Session,
     msg_stream: actix_ws::MessageStream,
-    mode: Option<Mode>,
+    query: QueryParams,
+    config: Arc<Config>,
+    metrics: Arc<Metrics>,
 ) {
+    let mode = query.mode.clone();
     let mut nickname = None;
     let mut conn_id = None;
+    let mut game_handler = None;
     let mut last_heartbeat = Instant::now();
-    let mut interval = interval(HEARTBEAT_INTERVAL);
+    // only users who close the connection on purpose leave right away
+    let mut left = false;
+    let mut interval = interval(config.heartbeat_interval());
+    let client_timeout = config.client_timeout();
+    let mut rate_limit = RateLimit::new(config.message_rate, config.message_burst);
 
     let (conn_tx, mut conn_rx) = mpsc::unbounded_channel();
 
     let msg_stream = msg_stream
-        .max_frame_size(128 * 1024)
+        .max_frame_size(config.max_frame_size)
         .aggregate_continuations()
-        .max_continuation_size(2 * 1024 * 1024);
+        .max_continuation_size(config.max_message_size);
 
     let mut msg_stream = pin!(msg_stream); // outbound
 
     let close_reason = loop {
-        let tick = pin!(interval.tick()); // ticks
+        // users in a room hear about the shutdown from the room itself
+        let joined = conn_id.is_some();
+        let closed = pin!(async {
+            if joined {
+                pending().await
+            } else {
+                rooms.closed().await
+            }
+        });
+        let tick = pin!(interval.tick());
+        let tick = pin!(select(tick, closed)); // ticks & shutdown
         let msg_rx = pin!(conn_rx.recv()); // inbound
         let messages = pin!(select(msg_stream.next(), msg_rx)); // inbound & outbound
 
@@ -118,7 +336,7 @@ pub async fn init(
                 match msg {
                     AggregatedMessage::Ping(bytes) => {
                         last_heartbeat = Instant::now();
-                        session.pong(&bytes).await.expect("failed to send pong");
+                        let _ = session.pong(&bytes).await;
                     }
 
                     AggregatedMessage::Pong(_) => {
@@ -127,23 +345,59 @@ pub async fn init(
 
                     // text message from client
                     AggregatedMessage::Text(text) => {
-                        let inbound = parse_inbound_message(&text, &mode);
-                        if let InboundMessage::Unknown = inbound {
-                            log::error!("Unknown message: {}", text);
-                            continue;
+                        metrics.message_received(&mode);
+                        if !rate_limit.try_take() {
+                            break handle_error(
+                                Err(Error::TooManyMessages),
+                                None,
+                                nickname.is_some(),
+                                &mode,
+                                &mut session,
+                            )
+                            .await;
                         }
 
-                        let result = handle_text_message(
-                            &inbound,
-                            &mut nickname,
-                            &mut conn_id,
-                            &game_handler,
-                            &conn_tx,
-                        )
-                        .await;
+                        let (id, result) = match parse_inbound_message(&text, &mode) {
+                            Ok(Request {
+                                id: None,
+                                message: InboundMessage::Unknown,
+                            }) => {
+                                log::error!("Unknown message: {}", text);
+                                continue;
+                            }
+                            // unless the client waits for an answer
+                            Ok(Request {
+                                id,
+                                message: InboundMessage::Unknown,
+                            }) => (
+                                id,
+                                Err(Error::InvalidCommand(format!("Unknown message: {}", text))),
+                            ),
+                            Ok(Request { id, message }) => {
+                                let result = handle_text_message(
+                                    &message,
+                                    &mut nickname,
+                                    &mut conn_id,
+                                    &mut game_handler,
+                                    &rooms,
+                                    &query,
+                                    &conn_tx,
+                                )
+                                .await;
+                                (id, result)
+                            }
+                            Err(err) => (None, Err(err)),
+                        };
+
+                        // queued behind whatever the room already sent back
+                        if let (Some(id), Ok(())) = (id, &result) {
+                            let _ = conn_tx.send(OutboundMessage::Ack { id });
+                        }
 
                         {
-                            let result = handle_error(result, &mut session).await;
+                            let result =
+                                handle_error(result, id, nickname.is_some(), &mode, &mut session)
+                                    .await;
                             if result.is_some() {
                                 break result;
                             }
@@ -154,7 +408,17 @@ pub async fn init(
                         log::warn!("unexpected binary message");
                     }
 
-                    AggregatedMessage::Close(reason) => break reason,
+                    AggregatedMessage::Close(reason) => {
+                        // browsers going away (e.g. reloading the page) may still come back
+                        left = !matches!(
+                            reason,
+                            Some(CloseReason {
+                                code: CloseCode::Away,
+                                ..
+                            })
+                        );
+                        break reason;
+                    }
                 }
             }
 
@@ -168,6 +432,7 @@ pub async fn init(
 
             // messages to send to client
             Either::Left((Either::Right((Some(answer), _)), _)) => {
+                let kicked = answer == OutboundMessage::Kicked;
                 let outbound = match mode {
                     Some(Mode::Json) => {
                         serde_json::to_string(&answer).expect("failed to serialize JSON message")
@@ -175,10 +440,40 @@ pub async fn init(
                     _ => answer.to_string(),
                 };
 
-                session
-                    .text(outbound)
-                    .await
-                    .expect("failed to send chat message");
+                let sent = session.text(outbound).await.is_ok();
+                if sent {
+                    metrics.message_sent(&mode);
+                }
+
+                // the game server already removed a kicked user
+                if kicked {
+                    conn_id = None;
+                    break Some(CloseReason {
+                        code: 1008.into(),
+                        description: Some(OutboundMessage::Kicked.to_string()),
+                    });
+                }
+
+                // and it is gone once it announces the shutdown
+                if let OutboundMessage::RoomClosed = answer {
+                    conn_id = None;
+                    break Some(CloseReason {
+                        code: CloseCode::Normal,
+                        description: Some(answer.to_string()),
+                    });
+                }
+                if let OutboundMessage::ShuttingDown(_) = answer {
+                    conn_id = None;
+                    break Some(CloseReason {
+                        code: CloseCode::Away,
+                        description: Some(answer.to_string()),
+                    });
+                }
+
+                // the client is gone, but may still come back
+                if !sent {
+                    break None;
+                }
             }
 
             Either::Left((Either::Right((None, _)), _)) => unreachable!(
@@ -186,11 +481,11 @@ pub async fn init(
             ),
 
             // heartbeat internal tick
-            Either::Right((_inst, _)) => {
+            Either::Right((Either::Left((_inst, _)), _)) => {
                 // if no heartbeat ping/pong received recently, close the connection
-                if Instant::now().duration_since(last_heartbeat) > CLIENT_TIMEOUT {
+                if Instant::now().duration_since(last_heartbeat) > client_timeout {
                     log::info!(
-                        "client has not sent heartbeat in over {CLIENT_TIMEOUT:?}; disconnecting"
+                        "client has not sent heartbeat in over {client_timeout:?}; disconnecting"
                     );
                     break None;
                 }
@@ -198,13 +493,26 @@ pub async fn init(
                 // send heartbeat ping
                 let _ = session.ping(b"").await;
             }
+
+            // the server is shutting down before the user joined a room
+            Either::Right((Either::Right(((), _)), _)) => {
+                break Some(CloseReason {
+                    code: CloseCode::Away,
+                    description: Some(Error::ShuttingDown.to_string()),
+                });
+            }
         };
     };
 
-    if let Some(conn_id) = conn_id {
-        match game_handler.disconnect(&conn_id).await {
-            Ok(_) => {}
-            Err(err) => log::error!("failed to disconnect user: {:?}: {}", conn_id, err),
+    if let (Some(conn_id), Some(game_handler)) = (conn_id, game_handler) {
+        let result = if left {
+            game_handler.disconnect(&conn_id).await
+        } else {
+            game_handler.suspend(&conn_id).await
+        };
+
+        if let Err(err) = result {
+            log::error!("failed to disconnect user: {:?}: {}", conn_id, err);
         }
     }
 
@@ -220,34 +528,116 @@ mod tests {
     async fn test_parse_inbound_message_json() {
         let mode = Some(Mode::Json);
 
+        let text = &json!({"hello": {"version": 1}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Hello {
+                version: 1,
+                features: Vec::new(),
+            }
+        );
+
         let text = &json!({"connect": {"nickname": "Player1"}}).to_string();
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::Connect {
-                nickname: "Player1".to_string()
+                nickname: "Player1".to_string(),
+                room: None,
+                observer: false,
+                password: None,
+                invite: None,
+            }
+        );
+
+        let text = &json!({"connect": {"nickname": "Player1", "room": "team-a"}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player1".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: None,
+                invite: None,
+            }
+        );
+
+        let text = &json!({"connect": {"nickname": "Player1", "observer": true}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player1".to_string(),
+                room: None,
+                observer: true,
+                password: None,
+                invite: None,
             }
         );
 
         let text = &json!({"setstatus": "Active"}).to_string();
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::SetStatus(shared::UserStatus::Active)
         );
 
         let text = &json!({"vote": {"value": "2"}}).to_string();
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::Vote {
-                value: shared::Vote::Option(2)
+                value: shared::Vote::from(2)
+            }
+        );
+
+        let text = &json!({"setdeck": {"name": "custom", "cards": ["S", "M", "L"]}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::SetDeck("S,M,L".parse().unwrap()));
+
+        let text = &json!({"starttimer": {"seconds": 60}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::StartTimer {
+                seconds: 60,
+                reveal: false
             }
         );
 
         let text = &json!({"unknown": "message"}).to_string();
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(result, InboundMessage::Unknown);
+
+        // requests keep their id, even when nothing else makes sense
+        let text = &json!({"id": 7, "vote": {"value": "5"}}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap();
+        assert_eq!(
+            result,
+            Request {
+                id: Some(7),
+                message: InboundMessage::Vote {
+                    value: shared::Vote::from(5)
+                }
+            }
+        );
+
+        let text = &json!({"id": 8, "reveal": null}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap();
+        assert_eq!(result.id, Some(8));
+        assert_eq!(result.message, InboundMessage::Reveal);
+
+        let text = &json!("reveal").to_string();
+        let result = parse_inbound_message(text, &mode).unwrap();
+        assert_eq!(result.id, None);
+        assert_eq!(result.message, InboundMessage::Reveal);
+
+        let text = &json!({"id": 9, "dance": true}).to_string();
+        let result = parse_inbound_message(text, &mode).unwrap();
+        assert_eq!(result.id, Some(9));
+        assert_eq!(result.message, InboundMessage::Unknown);
     }
 
     #[tokio::test]
@@ -255,41 +645,266 @@ mod tests {
         let mode = None;
 
         let text = "/join Player1";
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::Connect {
-                nickname: "Player1".to_string()
+                nickname: "Player1".to_string(),
+                room: None,
+                observer: false,
+                password: None,
+                invite: None,
             }
         );
 
+        let text = "/join Player One @team-a";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player One".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: None,
+                invite: None,
+            }
+        );
+
+        let text = "/watch Player1 @team-a";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player1".to_string(),
+                room: Some("team-a".to_string()),
+                observer: true,
+                password: None,
+                invite: None,
+            }
+        );
+
+        let text = "/join Player One @team-a correct horse";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player One".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: Some("correct horse".to_string()),
+                invite: None,
+            }
+        );
+
+        let text = "/join Player1 @team-a invite=123.abc";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player1".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: None,
+                invite: Some("123.abc".to_string()),
+            }
+        );
+
+        let text = "/password correct horse";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::SetPassword(Some("correct horse".to_string()))
+        );
+
+        // runs of spaces are part of the password
+        let text = "/password  a  b";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::SetPassword(Some("a  b".to_string()))
+        );
+
+        let text = "/join  Player  One  @team-a  a  b";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Connect {
+                nickname: "Player One".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: Some("a  b".to_string()),
+                invite: None,
+            }
+        );
+
+        let text = "/password off";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::SetPassword(None));
+
+        let text = "/invite";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Invite(60));
+
+        let text = "/invite 15";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Invite(15));
+
         let text = "/setaway";
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(result, InboundMessage::SetStatus(shared::UserStatus::Away));
 
         let text = "/setback";
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::SetStatus(shared::UserStatus::Active)
         );
 
         let text = "2";
-        let result = parse_inbound_message(text, &mode);
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::Vote {
-                value: shared::Vote::Option(2)
+                value: shared::Vote::from(2)
             }
         );
 
-        let text = "unknown message";
+        let text = "/deck t-shirt";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::SetDeck(shared::Deck::t_shirt()));
+
+        let text = "/deck ?, ½, 1";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::SetDeck("?,½,1".parse().unwrap()));
+
+        let text = "/deck nonsense";
+        let result = parse_inbound_message(text, &mode);
+        assert!(matches!(result, Err(Error::InvalidCommand(_))));
+
+        let text = "/kick Player Two";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Kick("Player Two".to_string()));
+
+        let text = "/promote";
+        let result = parse_inbound_message(text, &mode);
+        assert!(matches!(result, Err(Error::InvalidCommand(_))));
+
+        let text = "/lock";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::SetLocked(true));
+
+        let text = "/story add PROJ-1 Login page";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::AddStory(shared::Story::new("PROJ-1", "Login page"))
+        );
+
+        let text = "/story move PROJ-1 2";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::MoveStory {
+                key: "PROJ-1".to_string(),
+                position: 2
+            }
+        );
+
+        let text = "/accept 5";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Accept(shared::Vote::from(5)));
+
+        let text = "/timer 90 reveal";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::StartTimer {
+                seconds: 90,
+                reveal: true
+            }
+        );
+
+        let text = "/resume 0123abcd @team-a";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Resume {
+                token: "0123abcd".to_string(),
+                room: Some("team-a".to_string())
+            }
+        );
+
+        let text = "/timer stop";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::StopTimer);
+
+        let text = "/timer soon";
         let result = parse_inbound_message(text, &mode);
+        assert!(matches!(result, Err(Error::InvalidCommand(_))));
+
+        // anything else is a card, checked against the deck of the room
+        let text = "unknown message";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Vote {
+                value: shared::Vote::from("unknown message")
+            }
+        );
+
+        let text = "/vote 5";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::Vote {
+                value: shared::Vote::from(5)
+            }
+        );
+
+        let text = "/unvote";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
         assert_eq!(
             result,
             InboundMessage::Vote {
                 value: shared::Vote::Null
             }
         );
+
+        let text = "/nick Player Two";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(
+            result,
+            InboundMessage::SetNickname("Player Two".to_string())
+        );
+
+        let text = "/who";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Who);
+
+        let text = "/status";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Status);
+
+        let text = "/help";
+        let result = parse_inbound_message(text, &mode).unwrap().message;
+        assert_eq!(result, InboundMessage::Help);
+
+        let text = "/vote";
+        let result = parse_inbound_message(text, &mode);
+        assert_eq!(result.unwrap_err().to_string(), "Usage: /vote <card>");
+
+        let text = "/story shuffle";
+        let result = parse_inbound_message(text, &mode);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Usage: /story add <key> [title] or /story edit <key> <title>"));
+
+        let text = "/dance";
+        let result = parse_inbound_message(text, &mode);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unknown command /dance; type /help for the list"
+        );
     }
 }
@@ -0,0 +1,153 @@
+use crate::{
+    error::{Error, Result},
+    game::RoomId,
+};
+use serde::{Deserialize, Serialize};
+use shared::{Deck, RoundRecord, Story};
+use std::{
+    fmt::Debug,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// What outlives a room: its configuration, stories and finished rounds.
+/// Users and the votes of the round in progress are never stored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomState {
+    pub deck: Deck,
+    pub auto_reveal: bool,
+    pub stories: Vec<Story>,
+    pub current_story: Option<String>,
+    pub history: Vec<RoundRecord>,
+    /// Salted hash of the room password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+pub trait Storage: Debug + Send + Sync {
+    fn load(&self, room_id: &str) -> Result<Option<RoomState>>;
+    fn save(&self, room_id: &str, state: &RoomState) -> Result<()>;
+    fn rooms(&self) -> Result<Vec<RoomId>>;
+}
+
+/// Keeps every room as a JSON file in a directory.
+#[derive(Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).map_err(|err| Error::Storage(err.to_string()))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    // room ids are validated to be safe file names
+    fn path(&self, room_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", room_id))
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, room_id: &str) -> Result<Option<RoomState>> {
+        let content = match fs::read_to_string(self.path(room_id)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::Storage(err.to_string())),
+        };
+
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| Error::Storage(format!("{}: {}", room_id, err)))
+    }
+
+    fn save(&self, room_id: &str, state: &RoomState) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(state).map_err(|err| Error::Storage(err.to_string()))?;
+
+        // write aside and rename, so a crash never leaves a half written file
+        let tmp = self.dir.join(format!(".{}.json.tmp", room_id));
+        fs::write(&tmp, content)
+            .and_then(|_| fs::rename(&tmp, self.path(room_id)))
+            .map_err(|err| Error::Storage(err.to_string()))
+    }
+
+    fn rooms(&self) -> Result<Vec<RoomId>> {
+        let entries = fs::read_dir(&self.dir).map_err(|err| Error::Storage(err.to_string()))?;
+
+        let mut rooms = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_suffix(".json")
+                    .filter(|name| !name.starts_with('.'))
+                    .map(|name| name.to_string())
+            })
+            .collect::<Vec<_>>();
+        rooms.sort();
+
+        Ok(rooms)
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    rooms: std::sync::Mutex<std::collections::HashMap<RoomId, RoomState>>,
+}
+
+#[cfg(test)]
+impl Storage for MemoryStorage {
+    fn load(&self, room_id: &str) -> Result<Option<RoomState>> {
+        Ok(self.rooms.lock().unwrap().get(room_id).cloned())
+    }
+
+    fn save(&self, room_id: &str, state: &RoomState) -> Result<()> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .insert(room_id.to_string(), state.clone());
+
+        Ok(())
+    }
+
+    fn rooms(&self) -> Result<Vec<RoomId>> {
+        Ok(self.rooms.lock().unwrap().keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_storage() {
+        let dir = std::env::temp_dir().join(format!("omnipoker-{}", uuid::Uuid::new_v4()));
+        let storage = FileStorage::new(&dir).unwrap();
+
+        assert_eq!(storage.load("team-a").unwrap(), None);
+
+        let state = RoomState {
+            deck: Deck::t_shirt(),
+            stories: vec![Story::new("PROJ-1", "Login page")],
+            current_story: Some("PROJ-1".into()),
+            ..RoomState::default()
+        };
+        storage.save("team-a", &state).unwrap();
+        storage.save("team-b", &RoomState::default()).unwrap();
+
+        assert_eq!(storage.load("team-a").unwrap(), Some(state));
+        assert_eq!(storage.rooms().unwrap(), vec!["team-a", "team-b"]);
+
+        fs::write(dir.join("team-c.json"), "not json").unwrap();
+        assert!(matches!(storage.load("team-c"), Err(Error::Storage(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::{Error, Result};
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use rustls::ServerConfig;
+use std::{fs::File, io::BufReader, path::Path};
+
+fn open(path: &Path) -> Result<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|err| Error::InvalidConfig(format!("can't read {}: {}", path.display(), err)))
+}
+
+/// Builds the rustls configuration from a PEM certificate chain and private key.
+pub fn load_config(cert: &Path, key: &Path) -> Result<ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut open(cert)?)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| Error::InvalidConfig(format!("{}: {}", cert.display(), err)))?;
+    if certs.is_empty() {
+        return Err(Error::InvalidConfig(format!(
+            "{}: no certificate found",
+            cert.display()
+        )));
+    }
+
+    let private_key = rustls_pemfile::private_key(&mut open(key)?)
+        .map_err(|err| Error::InvalidConfig(format!("{}: {}", key.display(), err)))?
+        .ok_or_else(|| Error::InvalidConfig(format!("{}: no private key found", key.display())))?;
+
+    ServerConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| {
+            builder
+                .with_no_client_auth()
+                .with_single_cert(certs, private_key)
+        })
+        .map_err(|err| Error::InvalidConfig(format!("TLS: {}", err)))
+}
+
+/// Where a plain HTTP request lands on the TLS listener at `tls_port`.
+fn redirect_location(host: &str, tls_port: u16, path: &str) -> String {
+    let host = match host.rsplit_once(':') {
+        // leave the colons of a bare IPv6 address alone
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+
+    match tls_port {
+        443 => format!("https://{}{}", host, path),
+        _ => format!("https://{}:{}{}", host, tls_port, path),
+    }
+}
+
+/// Sends every request of the plain HTTP listener to the TLS one.
+pub async fn redirect(req: HttpRequest, tls_port: u16) -> HttpResponse {
+    let info = req.connection_info();
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+
+    HttpResponse::PermanentRedirect()
+        .insert_header((
+            header::LOCATION,
+            redirect_location(info.host(), tls_port, path),
+        ))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_load_config() {
+        let dir = std::env::temp_dir().join(format!("omnipoker-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        fs::write(&cert, certified.cert.pem()).unwrap();
+        fs::write(&key, certified.key_pair.serialize_pem()).unwrap();
+        assert!(load_config(&cert, &key).is_ok());
+
+        let err = load_config(&key, &key).unwrap_err();
+        assert!(err.to_string().ends_with("key.pem: no certificate found"));
+
+        let err = load_config(&cert, &dir.join("missing.pem")).unwrap_err();
+        assert!(err.to_string().contains("can't read"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_redirect_location() {
+        assert_eq!(
+            redirect_location("poker.example.com", 443, "/ws?mode=json"),
+            "https://poker.example.com/ws?mode=json"
+        );
+        assert_eq!(
+            redirect_location("127.0.0.1:8080", 8443, "/"),
+            "https://127.0.0.1:8443/"
+        );
+        assert_eq!(redirect_location("[::1]:80", 443, "/"), "https://[::1]/");
+    }
+}
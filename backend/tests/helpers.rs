@@ -1,7 +1,7 @@
 use futures_util::{SinkExt, StreamExt};
 use std::{
     io::{BufRead, BufReader},
-    process::{Child, Command, Stdio},
+    process::{Child, Command, ExitStatus, Stdio},
     sync::{Arc, Mutex},
     thread,
 };
@@ -18,6 +18,8 @@ pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 static PORT: Mutex<u16> = Mutex::new(8080);
 // That timeout should considering the compiling time on CI
 const TIMEOUT: Duration = Duration::from_secs(30);
+// Time for the capture thread to catch up with the latest lines on stdout
+const LOGS_GRACE: Duration = Duration::from_millis(200);
 
 pub fn get_port() -> String {
     let mut port = PORT.lock().unwrap();
@@ -41,6 +43,11 @@ impl ServerGuard {
     }
 
     pub async fn start(&mut self, port: &str) {
+        self.start_with_env(port, &[]).await;
+    }
+
+    /// Starts the server with some `OMNIPOKER_*` settings of its own.
+    pub async fn start_with_env(&mut self, port: &str, vars: &[(&str, &str)]) {
         if self.process.is_some() {
             panic!("Server is already running!");
         }
@@ -55,6 +62,7 @@ impl ServerGuard {
         self.process = Some(
             Command::new(command)
                 .args(&args)
+                .envs(vars.iter().copied())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .spawn()
@@ -94,7 +102,10 @@ impl ServerGuard {
             )
             .await
             {
-                Ok(Ok(_)) => {
+                Ok(Ok((mut ws_stream, _))) => {
+                    // close the probe cleanly so it doesn't show up in the server logs
+                    let _ = ws_stream.close(None).await;
+                    while let Some(Ok(_)) = ws_stream.next().await {}
                     println!("Server started successfully!");
                     return;
                 }
@@ -120,11 +131,9 @@ impl ServerGuard {
         // Spawn a thread to capture stdout logs
         thread::spawn(move || {
             let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let mut logs = logs.lock().unwrap();
-                    logs.push(line);
-                }
+            for line in reader.lines().map_while(Result::ok) {
+                let mut logs = logs.lock().unwrap();
+                logs.push(line);
             }
         });
 
@@ -134,21 +143,50 @@ impl ServerGuard {
         // Spawn a thread to capture stderr logs
         thread::spawn(move || {
             let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let mut errs = errs.lock().unwrap();
-                    errs.push(line);
-                }
+            for line in reader.lines().map_while(Result::ok) {
+                let mut errs = errs.lock().unwrap();
+                errs.push(line);
             }
         });
     }
 
+    /// Asks the server to shut down, as the orchestrator would.
+    #[allow(dead_code)] // not every test binary uses it
+    pub fn terminate(&self) {
+        let process = self
+            .process
+            .as_ref()
+            .expect("Server process is not running");
+
+        let status = Command::new("kill")
+            .args(["-TERM", &process.id().to_string()])
+            .status()
+            .expect("Failed to send SIGTERM");
+        assert!(status.success());
+    }
+
+    #[allow(dead_code)] // not every test binary uses it
+    pub fn wait(&mut self) -> ExitStatus {
+        self.process
+            .take()
+            .expect("Server process is not running")
+            .wait()
+            .expect("Failed to wait for the server")
+    }
+
     pub fn read_logs(&self) -> Vec<String> {
+        thread::sleep(LOGS_GRACE);
         let mut logs = self.logs.lock().unwrap();
         logs.drain(..).collect()
     }
 }
 
+impl Default for ServerGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Drop for ServerGuard {
     fn drop(&mut self) {
         if let Some(mut process) = self.process.take() {
@@ -197,3 +235,28 @@ pub async fn expect_message(receive_message: impl Fn(&str), ws_stream: &mut WsSt
         }
     }
 }
+
+#[allow(dead_code)] // not every test binary uses it
+pub async fn expect_close(expected_reason: &str, ws_stream: &mut WsStream) {
+    loop {
+        match timeout(TIMEOUT, ws_stream.next())
+            .await
+            .expect("Timed out waiting for message")
+            .expect("Failed to read message")
+        {
+            Ok(Message::Close(frame)) => {
+                let reason = frame.map(|frame| frame.reason.to_string());
+                assert_eq!(reason.as_deref(), Some(expected_reason));
+                return;
+            }
+            Ok(Message::Ping(_)) => {
+                log::debug!("Ignoring Ping message");
+                continue;
+            }
+            Ok(other) => {
+                panic!("Unexpected WebSocket message: {:?}", other);
+            }
+            _ => panic!("Unexpected WebSocket message"),
+        }
+    }
+}
@@ -1,9 +1,43 @@
-use helpers::{expect_message, get_port, send_message, ServerGuard};
+use helpers::{expect_close, expect_message, get_port, send_message, ServerGuard};
 use serde_json::json;
+use shared::PROTOCOL_VERSION;
 use tokio_tungstenite::connect_async;
 
 mod helpers;
 
+fn expect_deck(text: &str) {
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(text).unwrap(),
+        json!({"deck": {"name": "fibonacci", "cards": ["?", "1", "2", "3", "5", "8", "13"]}})
+    );
+}
+
+fn expect_resume_token(text: &str) {
+    let message = serde_json::from_str::<serde_json::Value>(text).unwrap();
+    assert!(message["resume_token"].is_string(), "{}", text);
+}
+
+fn expect_facilitator_token(text: &str) {
+    let message = serde_json::from_str::<serde_json::Value>(text).unwrap();
+    assert!(message["facilitator_token"].is_string(), "{}", text);
+}
+
+fn expect_user_list(users: &'static [(&'static str, bool)]) -> impl Fn(&str) {
+    move |text| {
+        let users = users
+            .iter()
+            .map(|(nickname, facilitator)| {
+                json!({"nickname": nickname, "facilitator": facilitator, "observer": false})
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(text).unwrap(),
+            json!({ "user_list": users })
+        );
+    }
+}
+
 fn get_server_url() -> (String, String) {
     let port = &get_port();
     (
@@ -33,11 +67,10 @@ async fn planning_poker_json() {
     )
     .await;
 
-    expect_message(
-        |text| assert_eq!(&text, &json!({"user_list": ["Player1"]}).to_string(),),
-        &mut ws_stream_1,
-    )
-    .await;
+    expect_message(expect_resume_token, &mut ws_stream_1).await;
+    expect_message(expect_deck, &mut ws_stream_1).await;
+    expect_message(expect_facilitator_token, &mut ws_stream_1).await;
+    expect_message(expect_user_list(&[("Player1", true)]), &mut ws_stream_1).await;
 
     send_message(
         &mut ws_stream_2,
@@ -45,13 +78,11 @@ async fn planning_poker_json() {
     )
     .await;
 
+    expect_message(expect_resume_token, &mut ws_stream_2).await;
+    expect_message(expect_deck, &mut ws_stream_2).await;
+
     expect_message(
-        |text| {
-            assert_eq!(
-                &text,
-                &json!({"user_list":["Player1","Player2"]}).to_string()
-            )
-        },
+        expect_user_list(&[("Player1", true), ("Player2", false)]),
         &mut ws_stream_1,
     )
     .await;
@@ -72,10 +103,10 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [
+                &json!({"votes_status": {"votes": [
                     ["Player1", "voted"],
                     ["Player2", "not voted"],
-                ]})
+                ]}})
                 .to_string(),
             )
         },
@@ -93,22 +124,43 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_result": [["Player1", "1"], ["Player2", "2"]]}).to_string()
+                &json!({"votes_result": {"votes": [["Player1", "1"], ["Player2", "2"]]}})
+                    .to_string()
             )
         },
         &mut ws_stream_1,
     )
     .await;
-
-    send_message(
+    expect_message(
+        |text| {
+            let stats = serde_json::from_str::<serde_json::Value>(text).unwrap();
+            assert_eq!(stats["stats"]["spread"], json!(1));
+            assert_eq!(stats["stats"]["consensus"], json!(false));
+        },
         &mut ws_stream_1,
-        &json!({"vote": {"value": "4"}}).to_string(),
     )
     .await;
 
+    send_message(&mut ws_stream_1, &json!("nextround").to_string()).await;
+
     expect_message(
-        |text| assert_eq!(&text, &json!({"your_vote": "not voted"}).to_string()),
+        |text| {
+            assert_eq!(
+                &text,
+                &json!({"votes_status": {"votes": [
+                    ["Player1", "not voted"],
+                    ["Player2", "not voted"],
+                ]}})
+                .to_string()
+            )
+        },
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(
         &mut ws_stream_1,
+        &json!({"vote": {"value": "4"}}).to_string(),
     )
     .await;
 
@@ -116,11 +168,7 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [
-                    ["Player1", "not voted"],
-                    ["Player2", "not voted"],
-                ]})
-                .to_string()
+                &json!({"error": "4 is not a card of this deck"}).to_string()
             )
         },
         &mut ws_stream_1,
@@ -143,10 +191,10 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [
+                &json!({"votes_status": {"votes": [
                     ["Player1", "voted"],
                     ["Player2", "not voted"],
-                ]})
+                ]}})
                 .to_string()
             )
         },
@@ -160,12 +208,7 @@ async fn planning_poker_json() {
         .expect("Failed to close connection");
 
     expect_message(
-        |text| {
-            assert_eq!(
-                &text,
-                &json!({"user_list":["Player1", "Player2"]}).to_string()
-            )
-        },
+        expect_user_list(&[("Player1", true), ("Player2", false)]),
         &mut ws_stream_2,
     )
     .await;
@@ -174,7 +217,7 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [["Player1", "voted"], ["Player2", "not voted"]]})
+                &json!({"votes_status": {"votes": [["Player1", "voted"], ["Player2", "not voted"]]}})
                     .to_string()
             )
         },
@@ -192,18 +235,28 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_result": [["Player1", "1"], ["Player2", "2"]]}).to_string()
+                &json!({"votes_result": {"votes": [["Player1", "1"], ["Player2", "2"]]}})
+                    .to_string()
             )
         },
         &mut ws_stream_2,
     )
     .await;
+    expect_message(
+        |text| {
+            let stats = serde_json::from_str::<serde_json::Value>(text).unwrap();
+            assert_eq!(stats["stats"]["spread"], json!(1));
+            assert_eq!(stats["stats"]["consensus"], json!(false));
+        },
+        &mut ws_stream_2,
+    )
+    .await;
 
     expect_message(
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [["Player1", "not voted"], ["Player2", "not voted"]]})
+                &json!({"votes_status": {"votes": [["Player1", "not voted"], ["Player2", "not voted"]]}})
                     .to_string()
             )
         },
@@ -215,7 +268,7 @@ async fn planning_poker_json() {
         |text| {
             assert_eq!(
                 &text,
-                &json!({"votes_status": [["Player1", "voted"], ["Player2", "not voted"]]})
+                &json!({"votes_status": {"votes": [["Player1", "voted"], ["Player2", "not voted"]]}})
                     .to_string()
             )
         },
@@ -223,18 +276,16 @@ async fn planning_poker_json() {
     )
     .await;
 
-    expect_message(
-        |text| assert_eq!(&text, &json!({"user_list": ["Player2"]}).to_string()),
-        &mut ws_stream_2,
-    )
-    .await;
+    expect_message(expect_facilitator_token, &mut ws_stream_2).await;
+    expect_message(expect_user_list(&[("Player2", true)]), &mut ws_stream_2).await;
+
+    // read the logs before Player2 leaves, so its disconnection can't race with them
+    let captured_logs = server_guard.read_logs();
 
     ws_stream_2
         .close(None)
         .await
         .expect("Failed to close connection");
-
-    let captured_logs = server_guard.read_logs();
     let expected_logs = vec![
         "Starting service", // welcome message
         "",
@@ -243,10 +294,11 @@ async fn planning_poker_json() {
         "directly on a browser",
         "to connect a websocket",
         "",
-        "Game started",               // first message
-        "User identified: Player1",   // Player1 identified
-        "User identified: Player2",   // Player2 identified
-        "User disconnected: Player1", // Player1 disconnected
+        "Game started",                 // first message
+        "User identified: Player1",     // Player1 identified
+        "User identified: Player2",     // Player2 identified
+        "4 is not a card of this deck", // Player1 voted off the deck
+        "User disconnected: Player1",   // Player1 disconnected
     ];
 
     assert_eq!(
@@ -265,3 +317,168 @@ async fn planning_poker_json() {
         );
     }
 }
+
+#[tokio::test]
+async fn test_handshake() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(
+        &mut ws_stream_1,
+        &json!({"hello": {"version": PROTOCOL_VERSION, "features": ["rooms"]}}).to_string(),
+    )
+    .await;
+    expect_message(
+        |text| {
+            let welcome = serde_json::from_str::<serde_json::Value>(text).unwrap();
+            assert_eq!(welcome["welcome"]["version"], json!(PROTOCOL_VERSION));
+            let features = welcome["welcome"]["features"].as_array().unwrap();
+            assert!(features.contains(&json!("rooms")));
+            assert!(features.contains(&json!("timers")));
+            assert!(features.contains(&json!("acks")));
+        },
+        &mut ws_stream_1,
+    )
+    .await;
+
+    // the handshake doesn't get in the way of joining
+    send_message(
+        &mut ws_stream_1,
+        &json!({"connect": {"nickname": "Player1"}}).to_string(),
+    )
+    .await;
+    expect_message(expect_resume_token, &mut ws_stream_1).await;
+
+    send_message(
+        &mut ws_stream_2,
+        &json!({"hello": {"version": PROTOCOL_VERSION + 1}}).to_string(),
+    )
+    .await;
+    expect_close(
+        &format!(
+            "Protocol version {} is not supported; this server speaks version {}",
+            PROTOCOL_VERSION + 1,
+            PROTOCOL_VERSION
+        ),
+        &mut ws_stream_2,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_acknowledgements() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(
+        &mut ws_stream,
+        &json!({"id": 1, "connect": {"nickname": "Player1"}}).to_string(),
+    )
+    .await;
+    expect_message(expect_resume_token, &mut ws_stream).await;
+    expect_message(expect_deck, &mut ws_stream).await;
+    expect_message(expect_facilitator_token, &mut ws_stream).await;
+    expect_message(expect_user_list(&[("Player1", true)]), &mut ws_stream).await;
+    expect_message(
+        |text| assert_eq!(&text, &json!({"ack": {"id": 1}}).to_string()),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(
+        &mut ws_stream,
+        &json!({"id": 2, "vote": {"value": "4"}}).to_string(),
+    )
+    .await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(text).unwrap(),
+                json!({"nack": {
+                    "id": 2,
+                    "code": "invalid_vote",
+                    "message": "4 is not a card of this deck",
+                }})
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, &json!({"id": 3, "dance": true}).to_string()).await;
+    expect_message(
+        |text| {
+            let nack = serde_json::from_str::<serde_json::Value>(text).unwrap();
+            assert_eq!(nack["nack"]["id"], json!(3));
+            assert_eq!(nack["nack"]["code"], json!("invalid_command"));
+        },
+        &mut ws_stream,
+    )
+    .await;
+
+    // messages without an id get the same answers as before
+    send_message(&mut ws_stream, &json!("stoptimer").to_string()).await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                &text,
+                &json!({"error": "There is no timer running"}).to_string()
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(
+        &mut ws_stream,
+        &json!({"id": 4, "setautoreveal": false}).to_string(),
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(&text, &json!({"auto_reveal": false}).to_string()),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(&text, &json!({"ack": {"id": 4}}).to_string()),
+        &mut ws_stream,
+    )
+    .await;
+
+    // joining twice is refused rather than acknowledged
+    send_message(
+        &mut ws_stream,
+        &json!({"id": 5, "connect": {"nickname": "Player2"}}).to_string(),
+    )
+    .await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(text).unwrap(),
+                json!({"nack": {
+                    "id": 5,
+                    "code": "already_joined",
+                    "message": "You are already in a room; reconnect to join another",
+                }})
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
+}
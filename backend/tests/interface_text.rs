@@ -1,9 +1,15 @@
-use helpers::{expect_message, get_port, send_message, ServerGuard};
+use helpers::{expect_close, expect_message, get_port, send_message, ServerGuard};
+use std::cell::RefCell;
 use tokio::net::TcpStream;
 use tokio_tungstenite::{connect_async, tungstenite::Error, MaybeTlsStream, WebSocketStream};
 
 mod helpers;
 
+const DECK: &str = "Deck: ?, 1, 2, 3, 5, 8, 13";
+const FACILITATOR: &str = "You are the facilitator; reclaim the role with /claim ";
+const RESUME: &str = "If you get disconnected, come back with /resume ";
+const WELCOME: &str = "Welcome to room ";
+
 fn get_server_url() -> (String, String) {
     let port = &get_port();
     (port.to_owned(), format!("ws://127.0.0.1:{}/ws", port))
@@ -25,11 +31,25 @@ async fn planning_poker() {
         .expect("Failed to connect to WebSocket");
 
     send_message(&mut ws_stream_1, "/join Player1").await;
-    expect_message(|text| assert_eq!(text, "Users: Player1"), &mut ws_stream_1).await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
 
     send_message(&mut ws_stream_2, "/join Player2").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_2).await;
     expect_message(
-        |text| assert_eq!(text, "Users: Player1, Player2"),
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
         &mut ws_stream_1,
     )
     .await;
@@ -48,19 +68,26 @@ async fn planning_poker() {
         &mut ws_stream_1,
     )
     .await;
-
-    send_message(&mut ws_stream_1, "4").await;
     expect_message(
-        |text| assert_eq!(text, "You voted: not voted"),
+        |text| assert_eq!(text, "Stats: min 1, max 2, mean 1.5, median 1, mode 1/2, spread 1; discuss: Player2 (highest) and Player1 (lowest)"),
         &mut ws_stream_1,
     )
     .await;
+
+    send_message(&mut ws_stream_1, "/next").await;
     expect_message(
         |text| assert_eq!(text, "Votes: Player1: not voted, Player2: not voted"),
         &mut ws_stream_1,
     )
     .await;
 
+    send_message(&mut ws_stream_1, "4").await;
+    expect_message(
+        |text| assert_eq!(text, "Error: 4 is not a card of this deck"),
+        &mut ws_stream_1,
+    )
+    .await;
+
     send_message(&mut ws_stream_1, "?").await;
     expect_message(|text| assert_eq!(text, "You voted: ?"), &mut ws_stream_1).await;
     expect_message(
@@ -75,10 +102,11 @@ async fn planning_poker() {
         .expect("Failed to close connection");
 
     expect_message(
-        |text| assert_eq!(text, "Users: Player1, Player2"),
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
         &mut ws_stream_2,
     )
     .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
     expect_message(
         |text| assert_eq!(text, "Votes: Player1: voted, Player2: not voted"),
         &mut ws_stream_2,
@@ -90,6 +118,11 @@ async fn planning_poker() {
         &mut ws_stream_2,
     )
     .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: min 1, max 2, mean 1.5, median 1, mode 1/2, spread 1; discuss: Player2 (highest) and Player1 (lowest)"),
+        &mut ws_stream_2,
+    )
+    .await;
     expect_message(
         |text| assert_eq!(text, "Votes: Player1: not voted, Player2: not voted"),
         &mut ws_stream_2,
@@ -100,14 +133,24 @@ async fn planning_poker() {
         &mut ws_stream_2,
     )
     .await;
-    expect_message(|text| assert_eq!(text, "Users: Player2"), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player2 (facilitator)"),
+        &mut ws_stream_2,
+    )
+    .await;
+
+    // read the logs before Player2 leaves, so its disconnection can't race with them
+    let captured_logs = server_guard.read_logs();
 
     ws_stream_2
         .close(None)
         .await
         .expect("Failed to close connection");
-
-    let captured_logs = server_guard.read_logs();
     let expected_logs = vec![
         "Starting service", // welcome message
         "",
@@ -116,10 +159,11 @@ async fn planning_poker() {
         "directly on a browser",
         "to connect a websocket",
         "",
-        "Game started",               // first message
-        "User identified: Player1",   // Player1 identified
-        "User identified: Player2",   // Player2 identified
-        "User disconnected: Player1", // Player1 disconnected
+        "Game started",                 // first message
+        "User identified: Player1",     // Player1 identified
+        "User identified: Player2",     // Player2 identified
+        "4 is not a card of this deck", // Player1 voted off the deck
+        "User disconnected: Player1",   // Player1 disconnected
     ];
 
     assert_eq!(captured_logs.len(), expected_logs.len());
@@ -151,11 +195,25 @@ async fn test_away() {
         .expect("Failed to connect to WebSocket");
 
     send_message(&mut ws_stream_1, "/join Player1").await;
-    expect_message(|text| assert_eq!(text, "Users: Player1"), &mut ws_stream_1).await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
 
     send_message(&mut ws_stream_2, "/join Player2").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_2).await;
     expect_message(
-        |text| assert_eq!(text, "Users: Player1, Player2"),
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
         &mut ws_stream_1,
     )
     .await;
@@ -170,11 +228,23 @@ async fn test_away() {
         &mut ws_stream_1,
     )
     .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on 2"),
+        &mut ws_stream_1,
+    )
+    .await;
 
     send_message(&mut ws_stream_1, "/setback").await;
     expect_message(|text| assert_eq!(text, "You are active"), &mut ws_stream_1).await;
     expect_message(
-        |text| assert_eq!(text, "Users: Player1, Player2"),
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "/next").await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: not voted, Player2: not voted"),
         &mut ws_stream_1,
     )
     .await;
@@ -193,41 +263,841 @@ async fn test_away() {
         &mut ws_stream_1,
     )
     .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on 1"),
+        &mut ws_stream_1,
+    )
+    .await;
 }
 
 #[tokio::test]
-async fn test_server_limit() {
+async fn test_rooms() {
+    let port = &get_port();
+    let server_url = format!("ws://127.0.0.1:{}/ws", port);
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(port).await;
+
+    let (mut ws_stream_1, _) = connect_async(format!("{}?room=team-a", server_url))
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_3, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
+
+    // same nickname, different room
+    send_message(&mut ws_stream_2, "/join Player1 @team-b").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_3, "/join Player2 @team-a").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_3).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_3).await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_2, "1").await;
+    expect_message(|text| assert_eq!(text, "You voted: 1"), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: 1"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on 1"),
+        &mut ws_stream_2,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "2").await;
+    expect_message(|text| assert_eq!(text, "You voted: 2"), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: voted, Player2: not voted"),
+        &mut ws_stream_1,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_deck() {
     let (port, server_url) = get_server_url();
     let mut server_guard = ServerGuard::new();
 
     server_guard.start(&port).await;
 
-    const SERVER_LIMIT: usize = 15;
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
 
-    let mut connections: [Option<WebSocketStream<MaybeTlsStream<TcpStream>>>; SERVER_LIMIT - 1] =
-        Default::default();
-    for i in 0..SERVER_LIMIT - 1 {
-        let (ws_stream, _) = connect_async(server_url.as_str())
-            .await
-            .expect("Failed to connect to WebSocket");
-        connections[i] = Some(ws_stream);
-    }
+    send_message(&mut ws_stream, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream).await;
 
-    let result = connect_async(server_url.as_str()).await;
+    send_message(&mut ws_stream, "/deck t-shirt").await;
+    expect_message(
+        |text| assert_eq!(text, "Deck: ?, XS, S, M, L, XL, XXL"),
+        &mut ws_stream,
+    )
+    .await;
 
-    // connection 15
-    assert!(result.is_ok(), "Unexpected result: {:?}", result);
+    send_message(&mut ws_stream, "XL").await;
+    expect_message(|text| assert_eq!(text, "You voted: XL"), &mut ws_stream).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: XL"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on XL"),
+        &mut ws_stream,
+    )
+    .await;
 
-    // connection 16 - should fail
-    match connect_async(server_url).await {
-        Err(Error::Http(response)) => {
-            assert_eq!(
-                response.status(),
-                actix_web::http::StatusCode::TOO_MANY_REQUESTS
-            );
-        }
-        result => {
-            panic!("Unexpected result: {:?}", result);
-        }
+    send_message(&mut ws_stream, "/deck ?, ½, 1, ☕").await;
+    expect_message(|text| assert_eq!(text, "Deck: ?, ½, 1, ☕"), &mut ws_stream).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: not voted"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "½").await;
+    expect_message(|text| assert_eq!(text, "You voted: ½"), &mut ws_stream).await;
+}
+
+#[tokio::test]
+async fn test_reveal() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
+
+    send_message(&mut ws_stream_2, "/join Player2").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "/autoreveal off").await;
+    expect_message(
+        |text| assert_eq!(text, "Auto-reveal is off"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "5").await;
+    expect_message(|text| assert_eq!(text, "You voted: 5"), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: voted, Player2: not voted"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_2, "8").await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: voted, Player2: voted"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    for expected in [DECK, "Users: Player1 (facilitator), Player2"] {
+        expect_message(|text| assert_eq!(text, expected), &mut ws_stream_2).await;
+    }
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
+    for expected in [
+        "Auto-reveal is off",
+        "Votes: Player1: voted, Player2: not voted",
+        "You voted: 8",
+        "Votes: Player1: voted, Player2: voted",
+    ] {
+        expect_message(|text| assert_eq!(text, expected), &mut ws_stream_2).await;
     }
+
+    send_message(&mut ws_stream_2, "/reveal").await;
+    expect_message(
+        |text| assert_eq!(text, "Error: Only the facilitator can do that"),
+        &mut ws_stream_2,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "/reveal").await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: 5, Player2: 8"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: min 5, max 8, mean 6.5, median 5, mode 5/8, spread 1; discuss: Player2 (highest) and Player1 (lowest)"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "/next").await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: not voted, Player2: not voted"),
+        &mut ws_stream_1,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_facilitator() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_3, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
+
+    send_message(&mut ws_stream_2, "/join Player2").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_1, "/lock").await;
+    expect_message(|text| assert_eq!(text, "Room is locked"), &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_3, "/join Player3").await;
+    expect_close("Room is locked", &mut ws_stream_3).await;
+
+    send_message(&mut ws_stream_1, "/kick Player2").await;
+    expect_message(
+        |text| assert_eq!(text, "You were removed from the room"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_close("You were removed from the room", &mut ws_stream_2).await;
+
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert_eq!(text, "Room is locked"), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_observer() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+
+    send_message(&mut ws_stream_2, "/watch Player2").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_2).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_2).await;
+    expect_message(
+        |text| assert_eq!(text, "You are observing"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator), Player2 (observer)"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_2, "3").await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                text,
+                "Error: Observers can't vote; use /setback to join the voting"
+            )
+        },
+        &mut ws_stream_2,
+    )
+    .await;
+
+    send_message(&mut ws_stream_1, "5").await;
+    expect_message(
+        |text| assert_eq!(text, "Votes: Player1: 5"),
+        &mut ws_stream_2,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on 5"),
+        &mut ws_stream_2,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_stories() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream).await;
+
+    send_message(&mut ws_stream, "/story add PROJ-1 Login page").await;
+    expect_message(
+        |text| assert_eq!(text, "Stories: PROJ-1 Login page (current)"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "/story add PROJ-2 Sign up").await;
+    expect_message(
+        |text| assert_eq!(text, "Stories: PROJ-1 Login page (current), PROJ-2 Sign up"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "3").await;
+    expect_message(|text| assert_eq!(text, "You voted: 3"), &mut ws_stream).await;
+    expect_message(
+        |text| assert_eq!(text, "Votes on PROJ-1: Player1: 3"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Stats: consensus on 3"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "/accept 3").await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                text,
+                "Stories: PROJ-1 Login page = 3, PROJ-2 Sign up (current)"
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Votes on PROJ-2: Player1: not voted"),
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/history").await;
+    expect_message(
+        |text| {
+            assert!(text.starts_with("History:\n1. "), "{}", text);
+            assert!(
+                text.ends_with(" PROJ-1 Login page: Player1: 3 => 3"),
+                "{}",
+                text
+            );
+        },
+        &mut ws_stream,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_server_limit() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    // every connection comes from the same address, so its own cap must be
+    // above the server's
+    server_guard
+        .start_with_env(&port, &[("OMNIPOKER_MAX_CONNECTIONS_PER_IP", "20")])
+        .await;
+
+    const SERVER_LIMIT: usize = 15;
+
+    let mut connections: [Option<WebSocketStream<MaybeTlsStream<TcpStream>>>; SERVER_LIMIT - 1] =
+        Default::default();
+    for connection in connections.iter_mut() {
+        let (ws_stream, _) = connect_async(server_url.as_str())
+            .await
+            .expect("Failed to connect to WebSocket");
+        *connection = Some(ws_stream);
+    }
+
+    let result = connect_async(server_url.as_str()).await;
+
+    // connection 15
+    assert!(result.is_ok(), "Unexpected result: {:?}", result);
+
+    // connection 16 - should fail
+    match connect_async(server_url).await {
+        Err(Error::Http(response)) => {
+            assert_eq!(
+                response.status(),
+                actix_web::http::StatusCode::TOO_MANY_REQUESTS
+            );
+        }
+        result => {
+            panic!("Unexpected result: {:?}", result);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_resume() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
+
+    let token = RefCell::new(String::new());
+    send_message(&mut ws_stream_2, "/join Player2").await;
+    expect_message(
+        |text| *token.borrow_mut() = text.strip_prefix(RESUME).unwrap().to_string(),
+        &mut ws_stream_2,
+    )
+    .await;
+    for expected in [DECK, "Users: Player1 (facilitator), Player2"] {
+        expect_message(|text| assert_eq!(text, expected), &mut ws_stream_2).await;
+    }
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_2, "3").await;
+    expect_message(|text| assert_eq!(text, "You voted: 3"), &mut ws_stream_2).await;
+
+    // the connection drops without a close frame
+    drop(ws_stream_2);
+
+    let (mut ws_stream_3, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_3, &format!("/resume {}", token.borrow())).await;
+    for expected in [
+        DECK,
+        "You voted: 3",
+        "Users: Player1 (facilitator), Player2",
+        "Votes: Player2: voted, Player1: not voted",
+    ] {
+        expect_message(|text| assert_eq!(text, expected), &mut ws_stream_3).await;
+    }
+
+    let (mut ws_stream_4, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_4, "/resume unknown").await;
+    expect_close(
+        "This session can't be resumed anymore; join again",
+        &mut ws_stream_4,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream).await;
+
+    // connected, but not in a room yet
+    let (mut idle_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    server_guard.terminate();
+    expect_message(
+        |text| assert_eq!(text, "The server is shutting down"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_close("The server is shutting down", &mut ws_stream).await;
+    expect_close("The server is shutting down", &mut idle_stream).await;
+
+    assert!(server_guard.wait().success());
+}
+
+#[tokio::test]
+async fn test_room_password() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream_1, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    send_message(&mut ws_stream_1, "/join Player1 @team-a").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_1).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_1).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream_1,
+    )
+    .await;
+    expect_message(|text| assert!(text.starts_with(WELCOME)), &mut ws_stream_1).await;
+
+    send_message(&mut ws_stream_1, "/password correct horse").await;
+    expect_message(
+        |text| assert_eq!(text, "Room is protected by a password"),
+        &mut ws_stream_1,
+    )
+    .await;
+
+    let (mut ws_stream_2, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+    send_message(&mut ws_stream_2, "/join Player2 @team-a wrong horse").await;
+    expect_close("Wrong password", &mut ws_stream_2).await;
+
+    send_message(&mut ws_stream_1, "/invite 5").await;
+    let invite = RefCell::new(String::new());
+    expect_message(
+        |text| {
+            let (_, token) = text
+                .split_once("?invite=")
+                .expect("the invite link has a token");
+            assert!(text.starts_with("Invite link, valid for 5 minutes: /r/team-a?invite="));
+            *invite.borrow_mut() = token.to_string();
+        },
+        &mut ws_stream_1,
+    )
+    .await;
+
+    // the invite link lets the user in, passing the room and invite in the query
+    let invite_url = format!("{}?room=team-a&invite={}", server_url, invite.borrow());
+    let (mut ws_stream_3, _) = connect_async(invite_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+    send_message(&mut ws_stream_3, "/join Player3").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream_3).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream_3).await;
+    expect_message(
+        |text| assert_eq!(text, "Room is protected by a password"),
+        &mut ws_stream_3,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_rate_limits() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    // 3 sessions are allowed from the same address by default
+    let mut ws_streams = Vec::new();
+    for _ in 0..3 {
+        let (ws_stream, _) = connect_async(server_url.as_str())
+            .await
+            .expect("Failed to connect to WebSocket");
+        ws_streams.push(ws_stream);
+    }
+
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+    expect_message(
+        |text| assert_eq!(text, "Error: Too many connections from your address"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_close("Too many connections from your address", &mut ws_stream).await;
+
+    // and a burst of 20 messages
+    let ws_stream = &mut ws_streams[0];
+    for _ in 0..21 {
+        send_message(ws_stream, "/unknown").await;
+    }
+    for _ in 0..20 {
+        expect_message(
+            |text| {
+                assert_eq!(
+                    text,
+                    "Error: Unknown command /unknown; type /help for the list"
+                )
+            },
+            ws_stream,
+        )
+        .await;
+    }
+    expect_message(
+        |text| assert_eq!(text, "Error: Too many messages, slow down"),
+        ws_stream,
+    )
+    .await;
+    expect_close("Too many messages, slow down", ws_stream).await;
+}
+
+#[tokio::test]
+async fn test_commands() {
+    let (port, server_url) = get_server_url();
+    let mut server_guard = ServerGuard::new();
+
+    server_guard.start(&port).await;
+
+    let (mut ws_stream, _) = connect_async(server_url.as_str())
+        .await
+        .expect("Failed to connect to WebSocket");
+
+    // help works before joining, the rest needs a room
+    send_message(&mut ws_stream, "/help").await;
+    expect_message(
+        |text| {
+            assert!(text.starts_with("Commands:\n/join <nickname>"));
+            assert!(text.contains("\n/nick <nickname>"));
+        },
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/who").await;
+    expect_message(
+        |text| assert_eq!(text, "Error: Join a room first with /join <nickname>"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "/join Player1").await;
+    expect_message(|text| assert!(text.starts_with(RESUME)), &mut ws_stream).await;
+    expect_message(|text| assert_eq!(text, DECK), &mut ws_stream).await;
+    expect_message(
+        |text| assert!(text.starts_with(FACILITATOR)),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Player1 (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    expect_message(
+        |text| {
+            assert!(text.starts_with(
+                "Welcome to room default, Player1! Vote by typing one of the cards: ?, 1, 2, 3, 5, 8, 13."
+            ));
+            assert!(text.ends_with("Type /help to see what they do."));
+        },
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "/dance").await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                text,
+                "Error: Unknown command /dance; type /help for the list"
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/vote").await;
+    expect_message(
+        |text| assert_eq!(text, "Error: Usage: /vote <card>"),
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/vote 4").await;
+    expect_message(
+        |text| assert_eq!(text, "Error: 4 is not a card of this deck"),
+        &mut ws_stream,
+    )
+    .await;
+
+    send_message(&mut ws_stream, "/nick Alice").await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Alice (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/who").await;
+    expect_message(
+        |text| assert_eq!(text, "Users: Alice (facilitator)"),
+        &mut ws_stream,
+    )
+    .await;
+    send_message(&mut ws_stream, "/status").await;
+    expect_message(
+        |text| {
+            assert_eq!(
+                text,
+                "You are Alice (facilitator) in room default: active, not voted, voting"
+            )
+        },
+        &mut ws_stream,
+    )
+    .await;
 }
@@ -0,0 +1,511 @@
+use crate::error::{Error, Result};
+use futures_util::{SinkExt, Stream, StreamExt};
+use shared::{InboundMessage, OutboundMessage, Request, UserStatus, Vote};
+use std::{
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    time::{interval, sleep, Instant, MissedTickBehavior},
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{protocol::CloseFrame, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How the client keeps the connection alive.
+#[derive(Clone, Debug)]
+pub struct ClientOptions {
+    /// Time between the pings sent to the server.
+    pub heartbeat_interval: Duration,
+    /// Time without hearing from the server before the connection counts as dropped.
+    pub client_timeout: Duration,
+    /// Times the client tries to come back after the connection dropped.
+    pub reconnect_attempts: u32,
+    /// Wait before the first attempt, growing with each of the next ones.
+    pub reconnect_delay: Duration,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(5),
+            client_timeout: Duration::from_secs(10),
+            reconnect_attempts: 5,
+            reconnect_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Message(OutboundMessage),
+    /// The connection dropped and the client is trying to resume the session.
+    Reconnecting {
+        attempt: u32,
+    },
+    Reconnected,
+    /// The session is over, with the reason the server gave, if any.
+    Closed(Option<String>),
+}
+
+/// A connection to an omnipoker server, speaking its JSON protocol.
+///
+/// Every connection opens with a `Hello`, so a server speaking another
+/// version of the protocol closes it right away.
+///
+/// A background task owns the websocket: it answers the heartbeats, and when
+/// the connection drops after joining a room, it reconnects and resumes the
+/// session with the token the server handed out.
+pub struct Client {
+    commands: mpsc::UnboundedSender<Request>,
+    events: mpsc::UnboundedReceiver<Event>,
+    last_id: AtomicU64,
+}
+
+impl Client {
+    pub async fn connect(url: &str) -> Result<Self> {
+        Self::connect_with(url, ClientOptions::default()).await
+    }
+
+    pub async fn connect_with(url: &str, options: ClientOptions) -> Result<Self> {
+        let url = json_url(url);
+        let (mut socket, _) = connect_async(url.as_str()).await?;
+        send(&mut socket, &InboundMessage::hello().into()).await?;
+
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(url, options, socket, commands_rx, events_tx));
+
+        Ok(Self {
+            commands: commands_tx,
+            events: events_rx,
+            last_id: AtomicU64::new(0),
+        })
+    }
+
+    pub fn send(&self, message: InboundMessage) -> Result<()> {
+        self.commands
+            .send(message.into())
+            .map_err(|_| Error::Closed)
+    }
+
+    /// Sends `message` and returns the id of the `Ack` or `Nack` the
+    /// server answers it with.
+    pub fn request(&self, message: InboundMessage) -> Result<u64> {
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.commands
+            .send(Request {
+                id: Some(id),
+                message,
+            })
+            .map_err(|_| Error::Closed)?;
+
+        Ok(id)
+    }
+
+    /// Joins `room`, or the default room of the server.
+    pub fn join(&self, nickname: &str, room: Option<&str>) -> Result<()> {
+        self.send(InboundMessage::Connect {
+            nickname: nickname.to_string(),
+            room: room.map(str::to_string),
+            observer: false,
+            password: None,
+            invite: None,
+        })
+    }
+
+    pub fn vote(&self, value: impl Into<Vote>) -> Result<()> {
+        self.send(InboundMessage::Vote {
+            value: value.into(),
+        })
+    }
+
+    pub fn remove_vote(&self) -> Result<()> {
+        self.vote(Vote::Null)
+    }
+
+    pub fn set_status(&self, status: UserStatus) -> Result<()> {
+        self.send(InboundMessage::SetStatus(status))
+    }
+
+    pub async fn next_event(&mut self) -> Option<Event> {
+        self.events.recv().await
+    }
+}
+
+impl Stream for Client {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// The server answers in JSON only when asked to.
+fn json_url(url: &str) -> String {
+    if url.contains("mode=") {
+        return url.to_string();
+    }
+
+    match url.contains('?') {
+        true => format!("{}&mode=json", url),
+        false => format!("{}?mode=json", url),
+    }
+}
+
+/// What it takes to resume the session on a new connection.
+#[derive(Debug, Default)]
+struct Session {
+    token: Option<String>,
+    room: Option<String>,
+}
+
+impl Session {
+    fn track_sent(&mut self, message: &InboundMessage) {
+        if let InboundMessage::Connect { room, .. } = message {
+            self.room = room.clone();
+        }
+    }
+
+    fn track_received(&mut self, message: &OutboundMessage) {
+        if let OutboundMessage::ResumeToken(token) = message {
+            self.token = Some(token.clone());
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Ended {
+    /// The client was dropped.
+    ByClient,
+    /// The server closed the connection on purpose.
+    ByServer(Option<String>),
+    Dropped,
+}
+
+async fn run(
+    url: String,
+    options: ClientOptions,
+    mut socket: Socket,
+    mut commands: mpsc::UnboundedReceiver<Request>,
+    events: mpsc::UnboundedSender<Event>,
+) {
+    let mut session = Session::default();
+
+    loop {
+        match drive(&mut socket, &options, &mut commands, &events, &mut session).await {
+            Ended::ByClient => {
+                let _ = socket.close(None).await;
+                return;
+            }
+            Ended::ByServer(reason) => {
+                let _ = events.send(Event::Closed(reason));
+                return;
+            }
+            Ended::Dropped => {
+                let Some(token) = session.token.clone() else {
+                    log::info!("Connection dropped before joining a room");
+                    let _ = events.send(Event::Closed(None));
+                    return;
+                };
+
+                let Some(new_socket) = reconnect(&url, &options, &events).await else {
+                    let _ = events.send(Event::Closed(None));
+                    return;
+                };
+                socket = new_socket;
+
+                let resume = InboundMessage::Resume {
+                    token,
+                    room: session.room.clone(),
+                };
+                if send(&mut socket, &InboundMessage::hello().into())
+                    .await
+                    .is_err()
+                    || send(&mut socket, &resume.into()).await.is_err()
+                {
+                    continue;
+                }
+                let _ = events.send(Event::Reconnected);
+            }
+        }
+    }
+}
+
+/// Relays the messages both ways until the connection ends.
+async fn drive(
+    socket: &mut Socket,
+    options: &ClientOptions,
+    commands: &mut mpsc::UnboundedReceiver<Request>,
+    events: &mpsc::UnboundedSender<Event>,
+    session: &mut Session,
+) -> Ended {
+    let mut heartbeat = interval(options.heartbeat_interval);
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_heard = Instant::now();
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(command) = command else {
+                    return Ended::ByClient;
+                };
+
+                session.track_sent(&command.message);
+                if let Err(err) = send(socket, &command).await {
+                    log::warn!("Failed to send {:?}: {}", command, err);
+                    return Ended::Dropped;
+                }
+            }
+            frame = socket.next() => {
+                last_heard = Instant::now();
+
+                match frame {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<OutboundMessage>(&text) {
+                            Ok(message) => {
+                                session.track_received(&message);
+                                let _ = events.send(Event::Message(message));
+                            }
+                            Err(err) => log::warn!("Unexpected message {}: {}", text, err),
+                        }
+                    }
+                    Some(Ok(Message::Close(frame))) => {
+                        let reason = frame.map(|CloseFrame { reason, .. }| reason.into_owned());
+                        return Ended::ByServer(reason);
+                    }
+                    // pings are answered by tungstenite itself
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        log::warn!("Connection error: {}", err);
+                        return Ended::Dropped;
+                    }
+                    None => return Ended::Dropped,
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > options.client_timeout {
+                    log::warn!("No news from the server in {:?}", options.client_timeout);
+                    return Ended::Dropped;
+                }
+
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    return Ended::Dropped;
+                }
+            }
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, message: &Request) -> Result<()> {
+    let text = serde_json::to_string(message).expect("failed to serialize JSON message");
+    socket.send(Message::Text(text)).await?;
+
+    Ok(())
+}
+
+async fn reconnect(
+    url: &str,
+    options: &ClientOptions,
+    events: &mpsc::UnboundedSender<Event>,
+) -> Option<Socket> {
+    for attempt in 1..=options.reconnect_attempts {
+        let _ = events.send(Event::Reconnecting { attempt });
+        sleep(options.reconnect_delay * attempt).await;
+
+        match connect_async(url).await {
+            Ok((socket, _)) => return Some(socket),
+            Err(err) => log::info!("Reconnection attempt {} failed: {}", attempt, err),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::ErrorCode;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn listen() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        (listener, url)
+    }
+
+    async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+        let (stream, _) = listener.accept().await.unwrap();
+        accept_async(stream).await.unwrap()
+    }
+
+    async fn receive(socket: &mut WebSocketStream<TcpStream>) -> InboundMessage {
+        loop {
+            let frame = tokio::time::timeout(TIMEOUT, socket.next())
+                .await
+                .expect("timed out waiting for the client")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = frame {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    async fn reply(socket: &mut WebSocketStream<TcpStream>, message: &OutboundMessage) {
+        let text = serde_json::to_string(message).unwrap();
+        socket.send(Message::Text(text)).await.unwrap();
+    }
+
+    async fn next_event(client: &mut Client) -> Event {
+        tokio::time::timeout(TIMEOUT, client.next_event())
+            .await
+            .expect("timed out waiting for an event")
+            .expect("the client stopped")
+    }
+
+    #[test]
+    fn test_json_url() {
+        assert_eq!(json_url("ws://host/ws"), "ws://host/ws?mode=json");
+        assert_eq!(
+            json_url("ws://host/ws?room=team-a"),
+            "ws://host/ws?room=team-a&mode=json"
+        );
+        assert_eq!(json_url("ws://host/ws?mode=json"), "ws://host/ws?mode=json");
+    }
+
+    #[tokio::test]
+    async fn test_join_and_vote() {
+        let (listener, url) = listen().await;
+        let (client, mut server) = tokio::join!(Client::connect(&url), accept(&listener));
+        let mut client = client.unwrap();
+
+        client.join("bot", Some("team-a")).unwrap();
+        client.vote("5").unwrap();
+        client.set_status(UserStatus::Away).unwrap();
+
+        assert_eq!(receive(&mut server).await, InboundMessage::hello());
+        assert_eq!(
+            receive(&mut server).await,
+            InboundMessage::Connect {
+                nickname: "bot".to_string(),
+                room: Some("team-a".to_string()),
+                observer: false,
+                password: None,
+                invite: None,
+            }
+        );
+        assert_eq!(
+            receive(&mut server).await,
+            InboundMessage::Vote {
+                value: Vote::from("5")
+            }
+        );
+        assert_eq!(
+            receive(&mut server).await,
+            InboundMessage::SetStatus(UserStatus::Away)
+        );
+
+        reply(&mut server, &OutboundMessage::YourVote(Vote::from("5"))).await;
+        assert_eq!(
+            next_event(&mut client).await,
+            Event::Message(OutboundMessage::YourVote(Vote::from("5")))
+        );
+
+        server.close(None).await.unwrap();
+        assert_eq!(next_event(&mut client).await, Event::Closed(None));
+        assert!(client.vote("8").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request() {
+        let (listener, url) = listen().await;
+        let (client, mut server) = tokio::join!(Client::connect(&url), accept(&listener));
+        let mut client = client.unwrap();
+        receive(&mut server).await;
+
+        assert_eq!(client.request(InboundMessage::Reveal).unwrap(), 1);
+        assert_eq!(client.request(InboundMessage::NextRound).unwrap(), 2);
+
+        let frame = tokio::time::timeout(TIMEOUT, server.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Request>(frame.to_text().unwrap()).unwrap(),
+            Request {
+                id: Some(1),
+                message: InboundMessage::Reveal,
+            }
+        );
+
+        let nack = OutboundMessage::Nack {
+            id: 2,
+            code: ErrorCode::NotFacilitator,
+            message: "Only the facilitator can do that".to_string(),
+        };
+        reply(&mut server, &nack).await;
+        assert_eq!(next_event(&mut client).await, Event::Message(nack));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_drop() {
+        let (listener, url) = listen().await;
+        let options = ClientOptions {
+            reconnect_delay: Duration::from_millis(10),
+            ..ClientOptions::default()
+        };
+        let (client, mut server) =
+            tokio::join!(Client::connect_with(&url, options), accept(&listener));
+        let mut client = client.unwrap();
+
+        client.join("bot", Some("team-a")).unwrap();
+        receive(&mut server).await;
+        receive(&mut server).await;
+        reply(&mut server, &OutboundMessage::ResumeToken("token".into())).await;
+        next_event(&mut client).await;
+
+        // the connection goes away without a close frame
+        drop(server);
+        assert_eq!(
+            next_event(&mut client).await,
+            Event::Reconnecting { attempt: 1 }
+        );
+
+        let mut server = accept(&listener).await;
+        assert_eq!(receive(&mut server).await, InboundMessage::hello());
+        assert_eq!(
+            receive(&mut server).await,
+            InboundMessage::Resume {
+                token: "token".to_string(),
+                room: Some("team-a".to_string()),
+            }
+        );
+        assert_eq!(next_event(&mut client).await, Event::Reconnected);
+
+        // but a close frame ends the session for good
+        server
+            .close(Some(CloseFrame {
+                code: 1008.into(),
+                reason: "Kicked".into(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_event(&mut client).await,
+            Event::Closed(Some("Kicked".to_string()))
+        );
+    }
+}
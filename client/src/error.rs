@@ -0,0 +1,26 @@
+use tokio_tungstenite::tungstenite;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Websocket(Box<tungstenite::Error>),
+    Closed,
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        Error::Websocket(Box::new(err))
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Websocket(err) => write!(f, "Websocket error: {}", err),
+            Error::Closed => write!(f, "The connection is closed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,25 @@
+//! Async client for the omnipoker websocket protocol, for bots and
+//! integrations running on tokio.
+//!
+//! ```no_run
+//! # async fn example() -> omnipoker_client::Result<()> {
+//! use omnipoker_client::{Client, Event};
+//!
+//! let mut client = Client::connect("ws://127.0.0.1:8080/ws").await?;
+//! client.join("bot", Some("team-a"))?;
+//!
+//! while let Some(event) = client.next_event().await {
+//!     if let Event::Message(message) = event {
+//!         println!("{}", message);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use client::*;
+pub use error::*;
+pub use shared::{ErrorCode, InboundMessage, OutboundMessage, UserStatus, Vote};
+
+mod client;
+mod error;
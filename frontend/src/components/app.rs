@@ -13,11 +13,15 @@ pub fn app() -> Html {
         state,
         ws_sink,
         on_nickname_change,
+        on_password_change,
+        on_observer_change,
         connect_callback,
         on_vote,
         on_remove_vote,
         on_set_away,
         on_away_back,
+        on_reveal,
+        on_next_round,
     } = use_planning_poker();
 
     html! {
@@ -29,6 +33,8 @@ pub fn app() -> Html {
                     <ConnectScreen
                         {connect_callback}
                         {on_nickname_change}
+                        {on_password_change}
+                        {on_observer_change}
                         nickname={state.nickname.clone()}
                         error_message={state.error_box.clone().unwrap_or_default()}
                     />
@@ -36,21 +42,44 @@ pub fn app() -> Html {
                 (Some(_), Screens::Home) => html! { <p class="loading">{"Loading..."}</p> },
                 (Some(_), Screens::Game) => html! {
                     <>
+                        if let Some(notice) = &state.notice {
+                            <p class="notice">{ notice }</p>
+                        }
+                        if let Some(story) = state.current_story() {
+                            <p class="current-story">
+                                <strong>{ &story.key }</strong>{ " " }
+                                if let Some(link) = &story.link {
+                                    <a href={link.clone()} target="_blank">{ &story.title }</a>
+                                } else {
+                                    { &story.title }
+                                }
+                            </p>
+                        }
+                        if let Some(remaining) = state.timer {
+                            <p class={classes!("timer", (remaining <= 10).then_some("ending"))}>
+                                { format!("{}:{:02}", remaining / 60, remaining % 60) }
+                            </p>
+                        }
                         <PokerStage
                             stage={state.stage.clone()}
+                            stats={state.stats.clone()}
                             is_rollback={state.is_rollback}
                             your_vote={state.your_vote.clone()}
                             nickname={state.nickname.clone()}
                             {on_remove_vote}
                         />
-                        <UserList user_list={state.user_list} nickname={state.nickname} />
+                        <UserList user_list={state.user_list.clone()} nickname={state.nickname.clone()} />
                         <Hand
+                            facilitator={state.is_facilitator()}
+                            deck={state.deck}
                             your_vote={state.your_vote}
                             your_status={state.your_status}
                             stage={state.stage}
                             {on_vote}
                             {on_set_away}
                             {on_away_back}
+                            {on_reveal}
+                            {on_next_round}
                         />
                     </>
                 },
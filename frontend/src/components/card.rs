@@ -14,16 +14,14 @@ pub struct Props {
     pub on_vote: Option<Callback<String>>,
 }
 
+const SUITS: [&str; 4] = ["spades", "hearts", "diams", "clubs"];
+
 fn vote_to_rank(vote: &str) -> &str {
     match vote {
         "?" => "",
         "1" => "A",
-        "2" => "2",
-        "3" => "3",
-        "5" => "5",
-        "8" => "8",
         "13" => "K",
-        _ => "-",
+        rank => rank,
     }
 }
 
@@ -34,7 +32,9 @@ fn vote_to_suite(vote: &str) -> &str {
         "2" | "13" => "spades",
         "3" => "diams",
         "5" => "clubs",
-        _ => "-",
+        "-" => "-",
+        // cards from other decks get a stable suit derived from their label
+        label => SUITS[label.chars().map(|c| c as usize).sum::<usize>() % SUITS.len()],
     }
 }
 
@@ -50,8 +50,8 @@ fn suit_to_symbol(suit: &str) -> &str {
 
 #[function_component(Card)]
 pub fn card(props: &Props) -> Html {
-    let rank = vote_to_rank(&props.vote.as_deref().unwrap_or("-"));
-    let suit = vote_to_suite(&props.vote.as_deref().unwrap_or("-"));
+    let rank = vote_to_rank(props.vote.as_deref().unwrap_or("-"));
+    let suit = vote_to_suite(props.vote.as_deref().unwrap_or("-"));
     let suit_symbol = suit_to_symbol(suit);
     let rank_class = format!("rank-{}", rank.to_lowercase());
     let suit_class = suit.to_lowercase();
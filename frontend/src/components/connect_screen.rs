@@ -4,6 +4,8 @@ use yew::prelude::*;
 pub struct Props {
     pub connect_callback: Callback<SubmitEvent>,
     pub on_nickname_change: Callback<InputEvent>,
+    pub on_password_change: Callback<InputEvent>,
+    pub on_observer_change: Callback<Event>,
     pub nickname: Option<String>,
     pub error_message: Option<String>,
 }
@@ -21,6 +23,15 @@ pub fn connect_screen(props: &Props) -> Html {
                         maxlength="20"
                         value={props.nickname.clone()}
                     />
+                    <input
+                        type="password"
+                        placeholder="Room password, if any"
+                        oninput={props.on_password_change.clone()}
+                    />
+                    <label class="observer">
+                        <input type="checkbox" onchange={props.on_observer_change.clone()} />
+                        { "Join as an observer, without voting" }
+                    </label>
                     <p class="error">{ props.error_message.clone() }</p>
                 </form>
             </div>
@@ -1,11 +1,12 @@
 use super::card::Card;
 use crate::state::Stage;
-use shared::{Vote, VoteStatus};
+use shared::{Vote, VoteStats, VoteStatus};
 use yew::prelude::*;
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct Props {
     pub stage: Stage,
+    pub stats: Option<VoteStats>,
     pub is_rollback: bool,
     pub your_vote: Vote,
     pub nickname: Option<String>,
@@ -30,6 +31,11 @@ pub fn poker_stage(props: &Props) -> Html {
                                 }
                                 </ul>
                             </div>
+                            if let Some(stats) = &props.stats {
+                                <p class={classes!("stats", stats.consensus.then_some("consensus"))}>
+                                    { stats.to_string() }
+                                </p>
+                            }
                         </div>
                     },
                     Stage::Status(statuses) => {
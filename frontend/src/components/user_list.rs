@@ -1,8 +1,9 @@
+use shared::Participant;
 use yew::prelude::*;
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct Props {
-    pub user_list: Vec<String>,
+    pub user_list: Vec<Participant>,
     pub nickname: Option<String>,
 }
 
@@ -19,10 +20,10 @@ pub fn user_list(props: &Props) -> Html {
                         <ul>
                         { for props.user_list.iter().map(|user| html! {
                             if let Some(nickname) = &props.nickname {
-                                if user == nickname {
-                                    <li>{ user }{ " (you)"}</li>
+                                if &user.nickname == nickname {
+                                    <li>{ user.to_string() }{ " (you)"}</li>
                                 } else {
-                                    <li>{ user }</li>
+                                    <li>{ user.to_string() }</li>
                                 }
                             }
                         }) }
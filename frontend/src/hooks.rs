@@ -3,8 +3,13 @@ use crate::{
     ws::{connect_websocket, send_message, WebSocketSink},
 };
 use gloo_net::websocket::WebSocketError;
-use shared::{InboundMessage, OutboundMessage, Vote};
-use std::borrow::Borrow;
+use gloo_storage::{SessionStorage, Storage};
+use gloo_timers::future::TimeoutFuture;
+use shared::{
+    InboundMessage, OutboundMessage, UserStatus, Vote, CLOSE_AUTH_FAILED,
+    CLOSE_UNSUPPORTED_PROTOCOL,
+};
+use std::{borrow::Borrow, cell::Cell, rc::Rc};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
@@ -12,17 +17,175 @@ pub struct UsePlanningPokerReturn {
     pub state: State,
     pub ws_sink: UseStateHandle<Option<WebSocketSink>>,
     pub on_nickname_change: Callback<InputEvent>,
+    pub on_password_change: Callback<InputEvent>,
+    pub on_observer_change: Callback<Event>,
     pub connect_callback: Callback<SubmitEvent>,
     pub on_set_away: Callback<MouseEvent>,
     pub on_away_back: Callback<String>,
     pub on_vote: Callback<String>,
     pub on_remove_vote: Callback<String>,
+    pub on_reveal: Callback<MouseEvent>,
+    pub on_next_round: Callback<MouseEvent>,
+}
+
+const RESUME_TOKEN_KEY: &str = "omnipoker.resume_token";
+const NICKNAME_KEY: &str = "omnipoker.nickname";
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY_MS: u32 = 1_000;
+
+fn handle_outbound(state: &UseReducerHandle<State>, outbound: OutboundMessage) {
+    match outbound {
+        OutboundMessage::Welcome { version, features } => {
+            log::info!(
+                "Server speaks protocol {} with {}",
+                version,
+                features.join(", ")
+            );
+        }
+
+        OutboundMessage::ResumeToken(token) => {
+            let _ = SessionStorage::set(RESUME_TOKEN_KEY, token);
+        }
+
+        OutboundMessage::UserList(list) => {
+            state.dispatch(StateAction::UpdateUserList(list));
+        }
+
+        OutboundMessage::Deck(deck) => {
+            state.dispatch(StateAction::UpdateDeck(deck));
+        }
+
+        OutboundMessage::Stories { stories, current } => {
+            state.dispatch(StateAction::UpdateStories(stories, current));
+        }
+
+        OutboundMessage::VotesResult { votes: results, .. } => {
+            state.dispatch(StateAction::Result(Stage::Result(results)));
+        }
+
+        OutboundMessage::Stats(stats) => {
+            state.dispatch(StateAction::UpdateStats(stats));
+        }
+
+        OutboundMessage::TimerStarted {
+            seconds: remaining, ..
+        }
+        | OutboundMessage::TimerTick(remaining) => {
+            state.dispatch(StateAction::UpdateTimer(Some(remaining)));
+        }
+
+        OutboundMessage::TimerExpired | OutboundMessage::TimerStopped => {
+            state.dispatch(StateAction::UpdateTimer(None));
+        }
+
+        OutboundMessage::VotesStatus {
+            votes: statuses, ..
+        } => {
+            state.dispatch(StateAction::Status(Stage::Status(statuses)));
+        }
+
+        OutboundMessage::YourVote(vote) => {
+            state.dispatch(StateAction::YourVote(vote));
+        }
+
+        OutboundMessage::YourStatus(status) => {
+            state.dispatch(StateAction::YourStatus(status));
+        }
+
+        OutboundMessage::Notice(message) => {
+            state.dispatch(StateAction::Notice(message));
+        }
+
+        OutboundMessage::ShuttingDown(_) | OutboundMessage::RoomClosed => {
+            // the rooms don't survive the restart, so there is nothing to resume
+            SessionStorage::delete(RESUME_TOKEN_KEY);
+            state.dispatch(StateAction::ConnectError(outbound.to_string()));
+        }
+
+        _ => {}
+    }
+}
+
+/// Opens the websocket and sends `first_message` on it. When the connection
+/// drops, it comes back with the resume token, if the server gave one.
+fn open_session(
+    state: UseReducerHandle<State>,
+    ws_sink: UseStateHandle<Option<WebSocketSink>>,
+    first_message: InboundMessage,
+    attempt: u32,
+) {
+    let received = Rc::new(Cell::new(false));
+
+    let sink = connect_websocket(
+        {
+            let state = state.clone();
+            let received = received.clone();
+
+            move |outbound| {
+                received.set(true);
+                handle_outbound(&state, outbound);
+            }
+        },
+        {
+            let state = state.clone();
+            let ws_sink = ws_sink.clone();
+
+            move |err| {
+                ws_sink.set(None);
+
+                let token = SessionStorage::get::<String>(RESUME_TOKEN_KEY).ok();
+                // only count the attempts that never got through
+                let attempt = if received.get() { 0 } else { attempt + 1 };
+
+                match (err, token) {
+                    // closed by the server on purpose, e.g. kicked, resumed too late,
+                    // turned away by a protected room or by a newer server
+                    (WebSocketError::ConnectionClose(e), _)
+                        if e.code == 1008
+                            || e.code == CLOSE_AUTH_FAILED
+                            || e.code == CLOSE_UNSUPPORTED_PROTOCOL =>
+                    {
+                        SessionStorage::delete(RESUME_TOKEN_KEY);
+                        state.dispatch(StateAction::ConnectError(e.reason));
+                    }
+                    (_, Some(token)) if attempt <= MAX_RECONNECT_ATTEMPTS => {
+                        let state = state.clone();
+                        let ws_sink = ws_sink.clone();
+
+                        log::info!("Reconnecting, attempt {}", attempt);
+                        spawn_local(async move {
+                            TimeoutFuture::new(RECONNECT_DELAY_MS * attempt.max(1)).await;
+                            let message = InboundMessage::Resume { token, room: None };
+                            open_session(state, ws_sink, message, attempt);
+                        });
+                    }
+                    (WebSocketError::ConnectionClose(e), _) => {
+                        state.dispatch(StateAction::ConnectError(e.reason));
+                    }
+                    (WebSocketError::ConnectionError, _) => {
+                        state.dispatch(StateAction::ConnectError(
+                            "The server is unreachable".to_string(),
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        },
+    );
+
+    if let Some(sink) = sink {
+        ws_sink.set(Some(sink.clone()));
+
+        log::info!("Connected to websocket");
+        spawn_local(async move {
+            send_message(&sink, &InboundMessage::hello()).await;
+            send_message(&sink, &first_message).await;
+        });
+    }
 }
 
 #[hook]
 pub fn use_planning_poker() -> UsePlanningPokerReturn {
-    use shared::UserStatus;
-
     let ws_sink = use_state(|| None);
     let state = use_reducer(State::default);
 
@@ -36,82 +199,78 @@ pub fn use_planning_poker() -> UsePlanningPokerReturn {
         })
     };
 
+    let on_password_change = {
+        let state = state.clone();
+
+        Callback::from(move |event: InputEvent| {
+            if let Some(input) = event.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let password = input.value();
+                state.dispatch(StateAction::UpdatePassword(
+                    (!password.is_empty()).then_some(password),
+                ));
+            }
+        })
+    };
+
+    let on_observer_change = {
+        let state = state.clone();
+
+        Callback::from(move |event: Event| {
+            if let Some(input) = event.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.dispatch(StateAction::YourStatus(if input.checked() {
+                    UserStatus::Observer
+                } else {
+                    UserStatus::Active
+                }));
+            }
+        })
+    };
+
+    // reattach to the previous session after the page is reloaded
+    {
+        let state = state.clone();
+        let ws_sink = ws_sink.clone();
+
+        use_effect_with((), move |_| {
+            if let (Ok(token), Ok(nickname)) = (
+                SessionStorage::get::<String>(RESUME_TOKEN_KEY),
+                SessionStorage::get::<String>(NICKNAME_KEY),
+            ) {
+                state.dispatch(StateAction::Connect(Some(nickname)));
+                open_session(
+                    state,
+                    ws_sink,
+                    InboundMessage::Resume { token, room: None },
+                    0,
+                );
+            }
+        });
+    }
+
     let connect_callback = {
         let ws_sink = ws_sink.clone();
         let state = state.clone();
 
         Callback::from(move |event: SubmitEvent| {
-            let state = state.clone();
             event.prevent_default();
-            if state.nickname.is_none() {
+            let Some(nickname) = state.nickname.clone() else {
                 state.dispatch(StateAction::ConnectError(
                     "Nickname is required".to_string(),
                 ));
                 return;
-            }
+            };
 
             if ws_sink.borrow().is_none() {
-                if let Some(sink) = connect_websocket(
-                    {
-                        let state = state.clone();
-
-                        move |outbound| match outbound {
-                            OutboundMessage::UserList(list) => {
-                                state.dispatch(StateAction::UpdateUserList(list));
-                            }
-
-                            OutboundMessage::VotesResult(results) => {
-                                state.dispatch(StateAction::Result(Stage::Result(results)));
-                            }
-
-                            OutboundMessage::VotesStatus(statuses) => {
-                                state.dispatch(StateAction::Status(Stage::Status(statuses)));
-                            }
-
-                            OutboundMessage::YourVote(vote) => {
-                                state.dispatch(StateAction::YourVote(vote));
-                            }
-
-                            OutboundMessage::YourStatus(status) => {
-                                state.dispatch(StateAction::YourStatus(status));
-                            }
-
-                            _ => {}
-                        }
-                    },
-                    {
-                        let state = state.clone();
-                        // error
-                        let ws_sink = ws_sink.clone();
-                        move |err| {
-                            ws_sink.set(None);
-                            match err {
-                                WebSocketError::ConnectionClose(e) => {
-                                    state.dispatch(StateAction::ConnectError(e.reason));
-                                }
-                                WebSocketError::ConnectionError => {
-                                    state.dispatch(StateAction::ConnectError(
-                                        "The server is unreachable".to_string(),
-                                    ));
-                                }
-                                _ => {}
-                            }
-                        }
-                    },
-                ) {
-                    ws_sink.set(Some(sink.clone()));
-                    let state = state.clone();
-
-                    log::info!("Connected to websocket");
-                    spawn_local(async move {
-                        log::info!("Sending nickname");
-                        if let Some(nickname) = state.nickname.clone() {
-                            log::info!("Sending nickname: {}", nickname);
-                            let message = InboundMessage::Connect { nickname };
-                            send_message(&sink, &message).await;
-                        }
-                    });
-                }
+                let _ = SessionStorage::set(NICKNAME_KEY, &nickname);
+                let message = InboundMessage::Connect {
+                    nickname,
+                    room: None,
+                    observer: state.your_status == UserStatus::Observer,
+                    password: state.password.clone(),
+                    // an invite link passes it along in the query string
+                    invite: None,
+                };
+                open_session(state.clone(), ws_sink.clone(), message, 0);
             }
         })
     };
@@ -175,14 +334,44 @@ pub fn use_planning_poker() -> UsePlanningPokerReturn {
         })
     };
 
+    let on_reveal = {
+        let ws_sink = ws_sink.clone();
+
+        Callback::from(move |_| {
+            if let Some(sink) = &*ws_sink {
+                let sink = sink.clone();
+                spawn_local(async move {
+                    send_message(&sink, &InboundMessage::Reveal).await;
+                });
+            }
+        })
+    };
+
+    let on_next_round = {
+        let ws_sink = ws_sink.clone();
+
+        Callback::from(move |_| {
+            if let Some(sink) = &*ws_sink {
+                let sink = sink.clone();
+                spawn_local(async move {
+                    send_message(&sink, &InboundMessage::NextRound).await;
+                });
+            }
+        })
+    };
+
     UsePlanningPokerReturn {
         state: (*state).clone(),
         ws_sink,
         on_nickname_change,
+        on_password_change,
+        on_observer_change,
         connect_callback,
         on_set_away,
         on_away_back,
         on_vote,
         on_remove_vote,
+        on_reveal,
+        on_next_round,
     }
 }
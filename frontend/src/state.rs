@@ -1,4 +1,4 @@
-use shared::{UserStatus, Vote, VoteStatus};
+use shared::{Deck, Participant, Story, UserStatus, Vote, VoteStats, VoteStatus};
 use std::rc::Rc;
 use yew::prelude::*;
 
@@ -37,9 +37,15 @@ pub enum StateAction {
     Status(Stage),
     Connect(Option<String>),
     ConnectError(String),
+    Notice(String),
+    UpdatePassword(Option<String>),
     YourVote(Vote),
     YourStatus(UserStatus),
-    UpdateUserList(Vec<String>),
+    UpdateUserList(Vec<Participant>),
+    UpdateDeck(Deck),
+    UpdateStories(Vec<Story>, Option<String>),
+    UpdateStats(VoteStats),
+    UpdateTimer(Option<u64>),
 }
 
 #[derive(Clone, Debug)]
@@ -50,12 +56,19 @@ pub enum Screens {
 #[derive(Clone)]
 pub struct State {
     pub stage: Stage,
+    pub stats: Option<VoteStats>,
     pub nickname: Option<String>,
+    pub password: Option<String>,
     pub error_box: Option<String>,
+    pub notice: Option<String>,
     pub your_vote: Vote,
     pub your_status: UserStatus,
     pub is_rollback: bool,
-    pub user_list: Vec<String>,
+    pub user_list: Vec<Participant>,
+    pub deck: Deck,
+    pub stories: Vec<Story>,
+    pub current_story: Option<String>,
+    pub timer: Option<u64>,
     pub screen: Screens,
 }
 
@@ -63,17 +76,38 @@ impl Default for State {
     fn default() -> Self {
         Self {
             stage: Stage::Init,
+            stats: None,
             nickname: None,
+            password: None,
             error_box: None,
+            notice: None,
             your_vote: Vote::Null,
             your_status: UserStatus::Active,
             is_rollback: false,
             user_list: Vec::new(),
+            deck: Deck::default(),
+            stories: Vec::new(),
+            current_story: None,
+            timer: None,
             screen: Screens::Home,
         }
     }
 }
 
+impl State {
+    pub fn current_story(&self) -> Option<&Story> {
+        self.stories
+            .iter()
+            .find(|story| Some(&story.key) == self.current_story.as_ref())
+    }
+
+    pub fn is_facilitator(&self) -> bool {
+        self.user_list
+            .iter()
+            .any(|user| user.facilitator && Some(&user.nickname) == self.nickname.as_ref())
+    }
+}
+
 impl Reducible for State {
     type Action = StateAction;
 
@@ -84,6 +118,7 @@ impl Reducible for State {
 
                 Self {
                     stage: new_stage.clone(),
+                    stats: None,
                     is_rollback: match (&new_stage, &old_stage) {
                         (new_stage @ Stage::Status(_), old_stage @ Stage::Status(_)) => {
                             new_stage.count_votes() < old_stage.count_votes()
@@ -104,7 +139,11 @@ impl Reducible for State {
                 ..(*self).clone()
             },
             StateAction::Connect(nickname) => Self {
-                nickname: nickname,
+                nickname,
+                ..(*self).clone()
+            },
+            StateAction::UpdatePassword(password) => Self {
+                password,
                 ..(*self).clone()
             },
             StateAction::ConnectError(err) => Self {
@@ -113,6 +152,10 @@ impl Reducible for State {
                 screen: Screens::Home,
                 ..(*self).clone()
             },
+            StateAction::Notice(message) => Self {
+                notice: Some(message),
+                ..(*self).clone()
+            },
             StateAction::YourVote(vote) => Self {
                 your_vote: vote,
                 ..(*self).clone()
@@ -126,6 +169,23 @@ impl Reducible for State {
                 screen: Screens::Game,
                 ..(*self).clone()
             },
+            StateAction::UpdateDeck(deck) => Self {
+                deck,
+                ..(*self).clone()
+            },
+            StateAction::UpdateStories(stories, current_story) => Self {
+                stories,
+                current_story,
+                ..(*self).clone()
+            },
+            StateAction::UpdateStats(stats) => Self {
+                stats: Some(stats),
+                ..(*self).clone()
+            },
+            StateAction::UpdateTimer(timer) => Self {
+                timer,
+                ..(*self).clone()
+            },
         }
         .into()
     }
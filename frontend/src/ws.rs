@@ -1,15 +1,39 @@
-use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use futures_util::{lock::Mutex, stream::SplitSink, SinkExt, StreamExt};
 use gloo_net::websocket::{futures::WebSocket, Message, WebSocketError};
 use shared::{InboundMessage, OutboundMessage};
-use std::{cell::RefCell, rc::Rc};
+use std::rc::Rc;
 use wasm_bindgen_futures::spawn_local;
-pub type WebSocketSink = Rc<RefCell<SplitSink<WebSocket, Message>>>;
+pub type WebSocketSink = Rc<Mutex<SplitSink<WebSocket, Message>>>;
+
+// forwards the page query string (e.g. `?room=team-a`) to the websocket,
+// along with the room of an invite link (`/r/team-a?invite=...`)
+fn websocket_url() -> String {
+    let location = web_sys::window().map(|window| window.location());
+    let search = location
+        .as_ref()
+        .and_then(|location| location.search().ok())
+        .unwrap_or_default();
+    let path = location
+        .and_then(|location| location.pathname().ok())
+        .unwrap_or_default();
+
+    let mut params = vec!["mode=json".to_string()];
+    if let Some(room) = path.strip_prefix("/r/").filter(|room| !room.is_empty()) {
+        params.push(format!("room={}", room));
+    }
+    match search.strip_prefix('?') {
+        Some(search) if !search.is_empty() => params.push(search.to_string()),
+        _ => {}
+    }
+
+    format!("/ws?{}", params.join("&"))
+}
 
 pub fn connect_websocket(
     on_message: impl Fn(OutboundMessage) + 'static,
     on_error: impl Fn(WebSocketError) + 'static,
 ) -> Option<WebSocketSink> {
-    let ws = WebSocket::open("/ws?mode=json").ok()?;
+    let ws = WebSocket::open(&websocket_url()).ok()?;
     let (write, mut read) = ws.split();
 
     spawn_local(async move {
@@ -29,13 +53,13 @@ pub fn connect_websocket(
         }
     });
 
-    Some(Rc::new(RefCell::new(write)))
+    Some(Rc::new(Mutex::new(write)))
 }
 
 pub async fn send_message(sink: &WebSocketSink, inbound: &InboundMessage) {
     if let Ok(message) = serde_json::to_string(inbound) {
         log::info!("Sending message: {}", message);
-        let mut sink = sink.borrow_mut();
+        let mut sink = sink.lock().await;
         let send_result = sink.send(Message::Text(message)).await;
 
         if let Err(err) = send_result {
@@ -0,0 +1,198 @@
+use super::vote::Vote;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+const MAX_CARDS: usize = 24;
+const MAX_LABEL_LEN: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Deck {
+    pub name: String,
+    pub cards: Vec<Vote>,
+}
+
+impl Deck {
+    fn preset(name: &str, cards: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            cards: cards.iter().map(|card| Vote::from(*card)).collect(),
+        }
+    }
+
+    pub fn fibonacci() -> Self {
+        Self::preset("fibonacci", &["?", "1", "2", "3", "5", "8", "13"])
+    }
+
+    pub fn modified_fibonacci() -> Self {
+        Self::preset(
+            "modified-fibonacci",
+            &[
+                "?", "0", "½", "1", "2", "3", "5", "8", "13", "20", "40", "100",
+            ],
+        )
+    }
+
+    pub fn t_shirt() -> Self {
+        Self::preset("t-shirt", &["?", "XS", "S", "M", "L", "XL", "XXL"])
+    }
+
+    pub fn powers_of_two() -> Self {
+        Self::preset(
+            "powers-of-two",
+            &["?", "1", "2", "4", "8", "16", "32", "64"],
+        )
+    }
+
+    pub fn hours() -> Self {
+        Self::preset(
+            "hours",
+            &["?", "1", "2", "4", "6", "8", "12", "16", "24", "40"],
+        )
+    }
+
+    pub fn presets() -> Vec<Self> {
+        vec![
+            Self::fibonacci(),
+            Self::modified_fibonacci(),
+            Self::t_shirt(),
+            Self::powers_of_two(),
+            Self::hours(),
+        ]
+    }
+
+    pub fn contains(&self, vote: &Vote) -> bool {
+        self.cards.contains(vote)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cards.len() < 2 || self.cards.len() > MAX_CARDS {
+            return Err(format!(
+                "A deck must have between 2 and {} cards",
+                MAX_CARDS
+            ));
+        }
+
+        if let Some(card) = self.cards.iter().find(|card| match card {
+            Vote::Null => true,
+            Vote::Unknown => false,
+            Vote::Option(label) => label.chars().count() > MAX_LABEL_LEN,
+        }) {
+            return Err(format!("Invalid card: {}", card));
+        }
+
+        if let Some((i, card)) = self
+            .cards
+            .iter()
+            .enumerate()
+            .find(|(i, card)| self.cards[..*i].contains(card))
+        {
+            return Err(format!("Card {} is repeated at position {}", card, i + 1));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::fibonacci()
+    }
+}
+
+/// Parses either the name of a preset or a comma separated list of cards.
+impl FromStr for Deck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(deck) = Self::presets().into_iter().find(|deck| deck.name == s) {
+            return Ok(deck);
+        }
+
+        if !s.contains(',') {
+            return Err(format!("Unknown deck: {}", s));
+        }
+
+        let deck = Self {
+            name: "custom".to_string(),
+            cards: s.split(',').map(Vote::from).collect(),
+        };
+        deck.validate()?;
+
+        Ok(deck)
+    }
+}
+
+impl std::fmt::Display for Deck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cards = self
+            .cards
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", cards.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_deck() {
+        let deck = Deck::default();
+
+        assert_eq!(deck.name, "fibonacci");
+        assert!(deck.contains(&Vote::Unknown));
+        assert!(deck.contains(&Vote::from(13)));
+        assert!(!deck.contains(&Vote::from(21)));
+        assert!(!deck.contains(&Vote::Null));
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        for deck in Deck::presets() {
+            assert_eq!(deck.validate(), Ok(()), "{}", deck.name);
+        }
+    }
+
+    #[test]
+    fn test_deck_from_str() {
+        assert_eq!("t-shirt".parse(), Ok(Deck::t_shirt()));
+        assert_eq!(" hours ".parse(), Ok(Deck::hours()));
+
+        let deck: Deck = "?, ½, 1, ☕".parse().unwrap();
+        assert_eq!(deck.name, "custom");
+        assert_eq!(
+            deck.cards,
+            vec![
+                Vote::Unknown,
+                Vote::from("½"),
+                Vote::from(1),
+                Vote::from("☕")
+            ]
+        );
+
+        assert!("unknown".parse::<Deck>().is_err());
+        assert!("1,".parse::<Deck>().is_err());
+        assert!("1,2,1".parse::<Deck>().is_err());
+        assert!("1,very long label".parse::<Deck>().is_err());
+    }
+
+    #[test]
+    fn test_deck_display() {
+        assert_eq!(Deck::t_shirt().to_string(), "?, XS, S, M, L, XL, XXL");
+    }
+
+    #[test]
+    fn test_deck_json() {
+        let json = json!({"name": "custom", "cards": ["?", "XL"]});
+        let deck: Deck = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(deck.cards, vec![Vote::Unknown, Vote::from("XL")]);
+        assert_eq!(serde_json::to_value(&deck).unwrap(), json);
+    }
+}
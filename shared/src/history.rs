@@ -0,0 +1,76 @@
+use super::{story::Story, vote::Vote};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoundRecord {
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub story: Option<Story>,
+    pub votes: Vec<(String, Vote)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Vote>,
+}
+
+impl RoundRecord {
+    pub fn story_label(&self) -> String {
+        self.story
+            .as_ref()
+            .map(|story| format!("{} {}", story.key, story.title))
+            .unwrap_or_default()
+    }
+
+    pub fn votes_label(&self) -> String {
+        self.votes
+            .iter()
+            .map(|(nickname, vote)| format!("{}: {}", nickname, vote))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl std::fmt::Display for RoundRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.timestamp)?;
+
+        if self.story.is_some() {
+            write!(f, " {}", self.story_label())?;
+        }
+
+        write!(f, ": {}", self.votes_label())?;
+
+        if let Some(estimate) = &self.estimate {
+            write!(f, " => {}", estimate)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_record_display() {
+        let mut record = RoundRecord {
+            timestamp: "2024-11-20T10:00:00Z".to_string(),
+            story: None,
+            votes: vec![
+                ("Player1".into(), Vote::from(3)),
+                ("Player2".into(), Vote::Null),
+            ],
+            estimate: None,
+        };
+        assert_eq!(
+            record.to_string(),
+            "2024-11-20T10:00:00Z: Player1: 3, Player2: not voted"
+        );
+
+        record.story = Some(Story::new("PROJ-1", "Login page"));
+        record.estimate = Some(Vote::from(3));
+        assert_eq!(
+            record.to_string(),
+            "2024-11-20T10:00:00Z PROJ-1 Login page: Player1: 3, Player2: not voted => 3"
+        );
+    }
+}
@@ -1,4 +1,8 @@
 pub use messages::*;
 
+mod deck;
+mod history;
 mod messages;
+mod stats;
+mod story;
 mod vote;
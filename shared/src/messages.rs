@@ -1,45 +1,465 @@
-pub use super::vote::{Vote, VoteStatus};
+pub use super::{
+    deck::Deck,
+    history::RoundRecord,
+    stats::VoteStats,
+    story::Story,
+    vote::{Vote, VoteStatus},
+};
 use bytestring::ByteString;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Version of the JSON protocol. Bump it on changes old clients can't follow.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What the server offers, announced in its `Welcome`.
+pub const FEATURES: &[&str] = &[
+    "rooms",
+    "decks",
+    "reveal",
+    "stats",
+    "timers",
+    "resume",
+    "facilitator",
+    "passwords",
+    "invites",
+    "observers",
+    "stories",
+    "history",
+    "acks",
+];
+
+/// Close code sent when a protected room refuses the password or the invite.
+pub const CLOSE_AUTH_FAILED: u16 = 4001;
+
+/// Close code sent to clients speaking another version of the protocol.
+pub const CLOSE_UNSUPPORTED_PROTOCOL: u16 = 4002;
+
+/// How long an invite lasts when `/invite` doesn't say.
+pub const DEFAULT_INVITE_MINUTES: u64 = 60;
+
+/// Text mode commands and what they do, as listed by `/help`.
+pub const TEXT_COMMANDS: &[(&str, &str)] = &[
+    (
+        "/join <nickname> [@room [password | invite=<token>]]",
+        "join a room",
+    ),
+    (
+        "/watch <nickname> [@room [password | invite=<token>]]",
+        "join without voting",
+    ),
+    (
+        "/resume <token> [@room]",
+        "get your seat back after a disconnection",
+    ),
+    ("/help", "list the commands"),
+    ("/who", "list the users in the room"),
+    ("/status", "show your status, your vote and the round"),
+    ("/vote <card>", "vote; typing the card alone does the same"),
+    ("/unvote", "take your vote back"),
+    ("/nick <nickname>", "change your nickname"),
+    ("/setaway", "step away from the voting"),
+    ("/setback", "come back to the voting"),
+    ("/reveal", "show the votes"),
+    ("/next", "start a new round"),
+    ("/autoreveal on|off", "reveal as soon as everyone voted"),
+    ("/deck <preset | card, card, ...>", "change the cards"),
+    (
+        "/timer <seconds> [reveal] | stop",
+        "start or stop a countdown",
+    ),
+    ("/claim <token>", "take the facilitator role back"),
+    ("/promote <nickname>", "hand the facilitator role over"),
+    ("/kick <nickname>", "remove a user from the room"),
+    ("/forceaway <nickname>", "mark an idle user as away"),
+    ("/clear", "clear the votes of the round"),
+    ("/lock", "stop new users joining"),
+    ("/unlock", "let new users join"),
+    (
+        "/password <password> | off",
+        "protect the room, or open it again",
+    ),
+    ("/invite [minutes]", "get a link that skips the password"),
+    ("/story add <key> [title]", "add a story to the queue"),
+    ("/story edit <key> <title>", "change the title of a story"),
+    ("/story link <key> [url]", "set the link of a story"),
+    ("/story desc <key> [text]", "set the description of a story"),
+    (
+        "/story move <key> <position>",
+        "move a story, counting from 1",
+    ),
+    ("/story remove <key>", "remove a story"),
+    ("/story select <key>", "estimate a story next"),
+    ("/stories", "list the stories"),
+    (
+        "/accept <estimate>",
+        "record the estimate of the current story",
+    ),
+    ("/history", "list the past rounds"),
+];
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum UserStatus {
     Active,
     Away,
+    Observer,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Participant {
+    pub nickname: String,
+    pub facilitator: bool,
+    #[serde(default)]
+    pub observer: bool,
+}
+
+impl fmt::Display for Participant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let roles = [
+            (self.facilitator, "facilitator"),
+            (self.observer, "observer"),
+        ]
+        .iter()
+        .filter(|(is, _)| *is)
+        .map(|(_, role)| *role)
+        .collect::<Vec<_>>();
+
+        match roles.len() {
+            0 => write!(f, "{}", self.nickname),
+            _ => write!(f, "{} ({})", self.nickname, roles.join(", ")),
+        }
+    }
+}
+
+/// Why the server turned a request down, for clients to act on.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidCommand,
+    NotJoined,
+    AlreadyJoined,
+    InvalidNickname,
+    NicknameInUse,
+    InvalidRoom,
+    RoomFull,
+    RoomLocked,
+    PasswordRequired,
+    WrongPassword,
+    InvalidInvite,
+    NotFacilitator,
+    InvalidToken,
+    UserNotFound,
+    InvalidDeck,
+    InvalidVote,
+    ObserverCannotVote,
+    RoundRevealed,
+    RoundNotRevealed,
+    InvalidStory,
+    StoryNotFound,
+    NoCurrentStory,
+    InvalidEstimate,
+    InvalidTimer,
+    NoTimerRunning,
+    UnsupportedProtocol,
+    RateLimited,
+    ShuttingDown,
+    Internal,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum OutboundMessage {
-    UserList(Vec<String>),
-    VotesResult(Vec<(String, Vote)>),
-    VotesStatus(Vec<(String, VoteStatus)>),
+    /// Answers the `Hello` of a client speaking the same protocol.
+    Welcome {
+        version: u32,
+        features: Vec<String>,
+    },
+    UserList(Vec<Participant>),
+    Deck(Deck),
+    AutoReveal(bool),
+    RoomLocked(bool),
+    RoomProtected(bool),
+    Invite {
+        link: String,
+        minutes: u64,
+    },
+    ResumeToken(String),
+    FacilitatorToken(String),
+    Kicked,
+    ShuttingDown(Option<String>),
+    RoomClosed,
+    Notice(String),
+    /// Greets a user joining in text mode.
+    Joined {
+        room: String,
+        nickname: String,
+        deck: Deck,
+    },
+    Help,
+    Status {
+        room: String,
+        nickname: String,
+        status: UserStatus,
+        vote: Vote,
+        facilitator: bool,
+        revealed: bool,
+    },
+    Stories {
+        stories: Vec<Story>,
+        current: Option<String>,
+    },
+    History(Vec<RoundRecord>),
+    VotesResult {
+        votes: Vec<(String, Vote)>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        story: Option<String>,
+    },
+    Stats(VoteStats),
+    TimerStarted {
+        seconds: u64,
+        reveal: bool,
+    },
+    TimerTick(u64),
+    TimerExpired,
+    TimerStopped,
+    VotesStatus {
+        votes: Vec<(String, VoteStatus)>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        story: Option<String>,
+    },
     YourVote(Vote),
     YourStatus(UserStatus),
     Unknown,
     Error(String),
+    /// The request with this id went through.
+    Ack {
+        id: u64,
+    },
+    /// The request with this id failed.
+    Nack {
+        id: u64,
+        code: ErrorCode,
+        message: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum InboundMessage {
-    Connect { nickname: String },
-    Vote { value: Vote },
+    /// Opens a JSON session with the protocol version of the client.
+    Hello {
+        version: u32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
+    },
+    Connect {
+        nickname: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        room: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        observer: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        invite: Option<String>,
+    },
+    Resume {
+        token: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        room: Option<String>,
+    },
+    Vote {
+        value: Vote,
+    },
     SetStatus(UserStatus),
+    SetNickname(String),
+    Help,
+    Who,
+    Status,
+    SetDeck(Deck),
+    Reveal,
+    NextRound,
+    SetAutoReveal(bool),
+    Claim(String),
+    Promote(String),
+    Kick(String),
+    ForceAway(String),
+    ClearVotes,
+    SetLocked(bool),
+    SetPassword(Option<String>),
+    Invite(u64),
+    AddStory(Story),
+    EditStory {
+        key: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        link: Option<String>,
+        #[serde(default)]
+        description: Option<String>,
+    },
+    MoveStory {
+        key: String,
+        position: usize,
+    },
+    RemoveStory(String),
+    SelectStory(String),
+    ListStories,
+    Accept(Vote),
+    History,
+    StartTimer {
+        seconds: u64,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        reveal: bool,
+    },
+    StopTimer,
     Unknown,
 }
 
+/// An inbound JSON message, with the id of the request when the client
+/// wants it acknowledged, e.g. `{"id": 7, "vote": {"value": "5"}}`.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Request {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub message: InboundMessage,
+}
+
+fn votes_heading(story: &Option<String>) -> String {
+    match story {
+        Some(key) => format!("Votes on {}", key),
+        None => "Votes".to_string(),
+    }
+}
+
 impl fmt::Display for OutboundMessage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let text = match self {
             OutboundMessage::UserList(users) => match users.len() {
                 0 => "Users: nobody is active".to_string(),
-                _ => format!("Users: {}", users.join(", ")),
+                _ => format!(
+                    "Users: {}",
+                    users
+                        .iter()
+                        .map(|user| user.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
             },
-            OutboundMessage::VotesResult(votes) => {
+            OutboundMessage::Deck(deck) => format!("Deck: {}", deck),
+            OutboundMessage::AutoReveal(true) => "Auto-reveal is on".to_string(),
+            OutboundMessage::AutoReveal(false) => "Auto-reveal is off".to_string(),
+            OutboundMessage::RoomLocked(true) => "Room is locked".to_string(),
+            OutboundMessage::RoomLocked(false) => "Room is unlocked".to_string(),
+            OutboundMessage::RoomProtected(true) => "Room is protected by a password".to_string(),
+            OutboundMessage::RoomProtected(false) => "Room is open to anyone".to_string(),
+            OutboundMessage::Invite { link, minutes } => {
+                format!("Invite link, valid for {} minutes: {}", minutes, link)
+            }
+            OutboundMessage::ResumeToken(token) => {
+                format!("If you get disconnected, come back with /resume {}", token)
+            }
+            OutboundMessage::FacilitatorToken(token) => {
+                format!(
+                    "You are the facilitator; reclaim the role with /claim {}",
+                    token
+                )
+            }
+            OutboundMessage::Kicked => "You were removed from the room".to_string(),
+            OutboundMessage::ShuttingDown(None) => "The server is shutting down".to_string(),
+            OutboundMessage::ShuttingDown(Some(hint)) => {
+                format!("The server is shutting down; {}", hint)
+            }
+            OutboundMessage::RoomClosed => "This room was closed by an operator".to_string(),
+            OutboundMessage::Notice(message) => format!("Notice from the operators: {}", message),
+            OutboundMessage::Welcome { version, features } => {
+                format!("Protocol version {}: {}", version, features.join(", "))
+            }
+            OutboundMessage::Joined {
+                room,
+                nickname,
+                deck,
+            } => format!(
+                "Welcome to room {}, {}! Vote by typing one of the cards: {}. Commands: {}. Type /help to see what they do.",
+                room,
+                nickname,
+                deck,
+                command_names().join(", ")
+            ),
+            OutboundMessage::Help => {
+                let width = TEXT_COMMANDS
+                    .iter()
+                    .map(|(usage, _)| usage.chars().count())
+                    .max()
+                    .unwrap_or(0);
+
                 format!(
-                    "Votes: {}",
+                    "Commands:\n{}",
+                    TEXT_COMMANDS
+                        .iter()
+                        .map(|(usage, about)| format!("{:width$}  {}", usage, about, width = width))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )
+            }
+            OutboundMessage::Status {
+                room,
+                nickname,
+                status,
+                vote,
+                facilitator,
+                revealed,
+            } => format!(
+                "You are {}{} in room {}: {}, {}, {}",
+                nickname,
+                if *facilitator { " (facilitator)" } else { "" },
+                room,
+                match status {
+                    UserStatus::Active => "active",
+                    UserStatus::Away => "away",
+                    UserStatus::Observer => "observing",
+                },
+                match vote {
+                    Vote::Null => "not voted".to_string(),
+                    vote => format!("voted {}", vote),
+                },
+                if *revealed {
+                    "votes revealed"
+                } else {
+                    "voting"
+                }
+            ),
+            OutboundMessage::Stories { stories, current } => match stories.len() {
+                0 => "Stories: none".to_string(),
+                _ => format!(
+                    "Stories: {}",
+                    stories
+                        .iter()
+                        .map(|story| match current {
+                            Some(key) if *key == story.key => format!("{} (current)", story),
+                            _ => story.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+            OutboundMessage::History(records) => match records.len() {
+                0 => "History: no rounds yet".to_string(),
+                _ => format!(
+                    "History:\n{}",
+                    records
+                        .iter()
+                        .enumerate()
+                        .map(|(i, record)| format!("{}. {}", i + 1, record))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+            },
+            OutboundMessage::VotesResult { votes, story } => {
+                format!(
+                    "{}: {}",
+                    votes_heading(story),
                     votes
                         .iter()
                         .map(|(nickname, vote)| format!("{}: {}", nickname, vote))
@@ -47,9 +467,21 @@ impl fmt::Display for OutboundMessage {
                         .join(", ")
                 )
             }
-            OutboundMessage::VotesStatus(statuses) => {
+            OutboundMessage::Stats(stats) => format!("Stats: {}", stats),
+            OutboundMessage::TimerStarted { seconds, reveal } => match reveal {
+                true => format!("Timer started: {}s, then the votes are revealed", seconds),
+                false => format!("Timer started: {}s", seconds),
+            },
+            OutboundMessage::TimerTick(seconds) => format!("Timer: {}s left", seconds),
+            OutboundMessage::TimerExpired => "Time is up".to_string(),
+            OutboundMessage::TimerStopped => "Timer stopped".to_string(),
+            OutboundMessage::VotesStatus {
+                votes: statuses,
+                story,
+            } => {
                 format!(
-                    "Votes: {}",
+                    "{}: {}",
+                    votes_heading(story),
                     statuses
                         .iter()
                         .map(|(nickname, status)| format!("{}: {}", nickname, status))
@@ -60,10 +492,12 @@ impl fmt::Display for OutboundMessage {
             OutboundMessage::YourStatus(status) => match status {
                 UserStatus::Active => "You are active".to_string(),
                 UserStatus::Away => "You are away".to_string(),
+                UserStatus::Observer => "You are observing".to_string(),
             },
             OutboundMessage::YourVote(vote) => {
                 format!("You voted: {}", vote)
             }
+            OutboundMessage::Error(message) => format!("Error: {}", message),
             _ => "Unknown message".to_string(),
         };
 
@@ -72,20 +506,221 @@ impl fmt::Display for OutboundMessage {
 }
 
 impl InboundMessage {
-    pub fn from_string(text: &str) -> Self {
-        let mut parts = text.split_whitespace();
-        let (command, values) = (parts.next(), parts.collect::<Vec<_>>().join(" "));
+    /// The `Hello` of a client built against this crate.
+    pub fn hello() -> Self {
+        InboundMessage::Hello {
+            version: PROTOCOL_VERSION,
+            features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+        }
+    }
+
+    /// Parses a line typed in text mode: a command, or else a card to vote
+    /// for. Commands that can't be understood give how to use them instead.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if !text.starts_with('/') {
+            return Ok(match text {
+                "" => InboundMessage::Unknown,
+                card => InboundMessage::Vote { value: card.into() },
+            });
+        }
+
+        // the rest of the line as typed, since passwords may hold runs of spaces
+        let (command, rest) = match text.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim_start()),
+            None => (text, ""),
+        };
+        let values = rest.split_whitespace().collect::<Vec<_>>().join(" ");
 
-        match (command, values.as_str()) {
-            (Some("/join"), nickname) => InboundMessage::Connect {
-                nickname: nickname.to_string(),
+        let message = match (command, values.as_str()) {
+            ("/join" | "/watch", _) => parse_join_command(rest, command == "/watch"),
+            ("/resume", "") => InboundMessage::Unknown,
+            ("/resume", values) => {
+                let (token, room) = match values.split_once(' ') {
+                    Some((token, room)) if room.len() > 1 && room.starts_with('@') => {
+                        (token, Some(room[1..].to_string()))
+                    }
+                    Some(_) => return Err(usage(command)),
+                    None => (values, None),
+                };
+
+                InboundMessage::Resume {
+                    token: token.to_string(),
+                    room,
+                }
+            }
+            ("/setaway", _) => InboundMessage::SetStatus(UserStatus::Away),
+            ("/setback", _) => InboundMessage::SetStatus(UserStatus::Active),
+            ("/deck", deck) => match deck.parse() {
+                Ok(deck) => InboundMessage::SetDeck(deck),
+                Err(_) => InboundMessage::Unknown,
+            },
+            ("/reveal", _) => InboundMessage::Reveal,
+            ("/next", _) => InboundMessage::NextRound,
+            ("/autoreveal", "on") => InboundMessage::SetAutoReveal(true),
+            ("/autoreveal", "off") => InboundMessage::SetAutoReveal(false),
+            ("/autoreveal", _) => InboundMessage::Unknown,
+            ("/claim" | "/promote" | "/kick" | "/forceaway", "") => InboundMessage::Unknown,
+            ("/claim", token) => InboundMessage::Claim(token.to_string()),
+            ("/promote", nickname) => InboundMessage::Promote(nickname.to_string()),
+            ("/kick", nickname) => InboundMessage::Kick(nickname.to_string()),
+            ("/forceaway", nickname) => InboundMessage::ForceAway(nickname.to_string()),
+            ("/clear", _) => InboundMessage::ClearVotes,
+            ("/story", values) => parse_story_command(values),
+            ("/stories", _) => InboundMessage::ListStories,
+            ("/history", _) => InboundMessage::History,
+            ("/timer", "stop") => InboundMessage::StopTimer,
+            ("/timer", values) => parse_timer_command(values),
+            ("/accept", "") => InboundMessage::Unknown,
+            ("/accept", value) => InboundMessage::Accept(value.into()),
+            ("/password", "") => InboundMessage::Unknown,
+            ("/password", "off") => InboundMessage::SetPassword(None),
+            ("/password", _) => InboundMessage::SetPassword(Some(rest.into())),
+            ("/invite", "") => InboundMessage::Invite(DEFAULT_INVITE_MINUTES),
+            ("/invite", minutes) => match minutes.parse() {
+                Ok(minutes) => InboundMessage::Invite(minutes),
+                Err(_) => InboundMessage::Unknown,
             },
-            (Some("/setaway"), _) => InboundMessage::SetStatus(UserStatus::Away),
-            (Some("/setback"), _) => InboundMessage::SetStatus(UserStatus::Active),
-            (Some(vote), _) => InboundMessage::Vote { value: vote.into() },
+            ("/lock", _) => InboundMessage::SetLocked(true),
+            ("/unlock", _) => InboundMessage::SetLocked(false),
+            ("/help", _) => InboundMessage::Help,
+            ("/who", _) => InboundMessage::Who,
+            ("/status", _) => InboundMessage::Status,
+            ("/vote", "") => InboundMessage::Unknown,
+            ("/vote", card) => InboundMessage::Vote { value: card.into() },
+            ("/unvote", _) => InboundMessage::Vote { value: Vote::Null },
+            ("/nick", "") => InboundMessage::Unknown,
+            ("/nick", nickname) => InboundMessage::SetNickname(nickname.to_string()),
             _ => InboundMessage::Unknown,
+        };
+
+        match message {
+            InboundMessage::Unknown => Err(usage(command)),
+            message => Ok(message),
         }
     }
+
+    pub fn from_string(text: &str) -> Self {
+        Self::parse(text).unwrap_or(InboundMessage::Unknown)
+    }
+}
+
+/// Every text command, once.
+fn command_names() -> Vec<&'static str> {
+    let mut names = Vec::new();
+    for (usage, _) in TEXT_COMMANDS {
+        let name = usage.split(' ').next().unwrap_or_default();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn usage(command: &str) -> String {
+    let usages = TEXT_COMMANDS
+        .iter()
+        .map(|(usage, _)| *usage)
+        .filter(|usage| usage.split(' ').next() == Some(command))
+        .collect::<Vec<_>>();
+
+    match usages.len() {
+        0 => format!("Unknown command {}; type /help for the list", command),
+        _ => format!("Usage: {}", usages.join(" or ")),
+    }
+}
+
+fn parse_story_command(text: &str) -> InboundMessage {
+    let mut parts = text.splitn(3, ' ');
+    let (command, key, value) = (parts.next(), parts.next(), parts.next().unwrap_or(""));
+
+    match (command, key, value) {
+        (Some("add"), Some(key), title) => InboundMessage::AddStory(Story::new(key, title)),
+        (Some("edit"), Some(key), title) if !title.is_empty() => InboundMessage::EditStory {
+            key: key.to_string(),
+            title: Some(title.to_string()),
+            link: None,
+            description: None,
+        },
+        (Some("link"), Some(key), link) => InboundMessage::EditStory {
+            key: key.to_string(),
+            title: None,
+            link: Some(link.to_string()),
+            description: None,
+        },
+        (Some("desc"), Some(key), description) => InboundMessage::EditStory {
+            key: key.to_string(),
+            title: None,
+            link: None,
+            description: Some(description.to_string()),
+        },
+        (Some("move"), Some(key), position) => match position.parse() {
+            Ok(position) => InboundMessage::MoveStory {
+                key: key.to_string(),
+                position,
+            },
+            Err(_) => InboundMessage::Unknown,
+        },
+        (Some("remove"), Some(key), "") => InboundMessage::RemoveStory(key.to_string()),
+        (Some("select"), Some(key), "") => InboundMessage::SelectStory(key.to_string()),
+        _ => InboundMessage::Unknown,
+    }
+}
+
+/// `NICKNAME [@ROOM [PASSWORD | invite=TOKEN]]`, where the nickname may have
+/// spaces and the password is kept as typed.
+fn parse_join_command(text: &str, observer: bool) -> InboundMessage {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let room_at = words
+        .iter()
+        .skip(1)
+        .position(|word| word.len() > 1 && word.starts_with('@'))
+        .map(|i| i + 1);
+
+    let Some(room_at) = room_at else {
+        return InboundMessage::Connect {
+            nickname: words.join(" "),
+            room: None,
+            observer,
+            password: None,
+            invite: None,
+        };
+    };
+
+    // whatever follows the room, as typed
+    let secret = (0..=room_at).fold(text, |rest, _| {
+        rest.trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest)
+    });
+    let secret = secret.trim_start();
+
+    let (password, invite) = match secret.strip_prefix("invite=") {
+        Some(invite) => (None, Some(invite.to_string())),
+        None if secret.is_empty() => (None, None),
+        None => (Some(secret.to_string()), None),
+    };
+
+    InboundMessage::Connect {
+        nickname: words[..room_at].join(" "),
+        room: Some(words[room_at][1..].to_string()),
+        observer,
+        password,
+        invite,
+    }
+}
+
+fn parse_timer_command(text: &str) -> InboundMessage {
+    let (seconds, reveal) = match text.split_once(' ') {
+        Some((seconds, "reveal")) => (seconds, true),
+        Some(_) => return InboundMessage::Unknown,
+        None => (text, false),
+    };
+
+    match seconds.parse() {
+        Ok(seconds) => InboundMessage::StartTimer { seconds, reveal },
+        Err(_) => InboundMessage::Unknown,
+    }
 }
 
 impl From<ByteString> for InboundMessage {
@@ -94,6 +729,12 @@ impl From<ByteString> for InboundMessage {
     }
 }
 
+impl From<InboundMessage> for Request {
+    fn from(message: InboundMessage) -> Self {
+        Request { id: None, message }
+    }
+}
+
 impl From<String> for InboundMessage {
     fn from(text: String) -> Self {
         InboundMessage::from_string(&text)
@@ -0,0 +1,220 @@
+use super::{deck::Deck, vote::Vote};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoteStats {
+    pub min: Option<Vote>,
+    pub max: Option<Vote>,
+    pub mean: Option<f64>,
+    pub median: Option<Vote>,
+    pub mode: Vec<Vote>,
+    /// Distance between the lowest and the highest vote, in cards of the deck.
+    pub spread: usize,
+    pub unknown: usize,
+    pub consensus: bool,
+    pub lowest: Vec<String>,
+    pub highest: Vec<String>,
+}
+
+fn numeric_value(vote: &Vote) -> Option<f64> {
+    match vote {
+        Vote::Option(label) if label == "½" => Some(0.5),
+        Vote::Option(label) => label.parse().ok(),
+        _ => None,
+    }
+}
+
+impl VoteStats {
+    pub fn compute(votes: &[(String, Vote)], deck: &Deck) -> Self {
+        let unknown = votes
+            .iter()
+            .filter(|(_, vote)| *vote == Vote::Unknown)
+            .count();
+
+        // votes paired with their position in the deck, lowest first
+        let mut cards = votes
+            .iter()
+            .filter(|(_, vote)| matches!(vote, Vote::Option(_)))
+            .filter_map(|(nickname, vote)| {
+                let position = deck.cards.iter().position(|card| card == vote)?;
+                Some((position, nickname, vote))
+            })
+            .collect::<Vec<_>>();
+        cards.sort_by_key(|(position, _, _)| *position);
+
+        let (Some(first), Some(last)) = (cards.first(), cards.last()) else {
+            return Self {
+                min: None,
+                max: None,
+                mean: None,
+                median: None,
+                mode: Vec::new(),
+                spread: 0,
+                unknown,
+                consensus: false,
+                lowest: Vec::new(),
+                highest: Vec::new(),
+            };
+        };
+        let (min_position, max_position) = (first.0, last.0);
+
+        let values = cards
+            .iter()
+            .map(|(_, _, vote)| numeric_value(vote))
+            .collect::<Option<Vec<_>>>();
+        let mean = values.map(|values| values.iter().sum::<f64>() / values.len() as f64);
+
+        let mut counts: Vec<(&Vote, usize)> = Vec::new();
+        for (_, _, vote) in &cards {
+            match counts.iter_mut().find(|(card, _)| card == vote) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((vote, 1)),
+            }
+        }
+        let top = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+        let spread = max_position - min_position;
+        let voters_at = |position: usize| {
+            cards
+                .iter()
+                .filter(|(card_position, _, _)| *card_position == position)
+                .map(|(_, nickname, _)| nickname.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            min: Some(first.2.clone()),
+            max: Some(last.2.clone()),
+            mean,
+            median: Some(cards[(cards.len() - 1) / 2].2.clone()),
+            mode: counts
+                .iter()
+                .filter(|(_, count)| *count == top)
+                .map(|(vote, _)| (*vote).clone())
+                .collect(),
+            spread,
+            unknown,
+            consensus: spread == 0 && unknown == 0,
+            lowest: if spread > 0 {
+                voters_at(min_position)
+            } else {
+                Vec::new()
+            },
+            highest: if spread > 0 {
+                voters_at(max_position)
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for VoteStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (Some(min), Some(max), Some(median)) = (&self.min, &self.max, &self.median) else {
+            return match self.unknown {
+                0 => write!(f, "nobody voted"),
+                unknown => write!(f, "{} unknown", unknown),
+            };
+        };
+
+        if self.consensus {
+            return write!(f, "consensus on {}", min);
+        }
+
+        write!(f, "min {}, max {}", min, max)?;
+        if let Some(mean) = self.mean {
+            write!(f, ", mean {:.1}", mean)?;
+        }
+        let mode = self
+            .mode
+            .iter()
+            .map(|vote| vote.to_string())
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            ", median {}, mode {}, spread {}",
+            median,
+            mode.join("/"),
+            self.spread
+        )?;
+
+        if self.unknown > 0 {
+            write!(f, ", {} unknown", self.unknown)?;
+        }
+
+        if !self.highest.is_empty() {
+            write!(
+                f,
+                "; discuss: {} (highest) and {} (lowest)",
+                self.highest.join(", "),
+                self.lowest.join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn votes(votes: &[(&str, &str)]) -> Vec<(String, Vote)> {
+        votes
+            .iter()
+            .map(|(nickname, vote)| (nickname.to_string(), Vote::from(*vote)))
+            .collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = VoteStats::compute(
+            &votes(&[("A", "1"), ("B", "3"), ("C", "3"), ("D", "8"), ("E", "?")]),
+            &Deck::default(),
+        );
+
+        assert_eq!(stats.min, Some(Vote::from(1)));
+        assert_eq!(stats.max, Some(Vote::from(8)));
+        assert_eq!(stats.mean, Some(3.75));
+        assert_eq!(stats.median, Some(Vote::from(3)));
+        assert_eq!(stats.mode, vec![Vote::from(3)]);
+        assert_eq!(stats.spread, 4);
+        assert_eq!(stats.unknown, 1);
+        assert!(!stats.consensus);
+        assert_eq!(stats.lowest, vec!["A"]);
+        assert_eq!(stats.highest, vec!["D"]);
+        assert_eq!(
+            stats.to_string(),
+            "min 1, max 8, mean 3.8, median 3, mode 3, spread 4, 1 unknown; discuss: D (highest) and A (lowest)"
+        );
+    }
+
+    #[test]
+    fn test_stats_consensus() {
+        let stats = VoteStats::compute(&votes(&[("A", "5"), ("B", "5")]), &Deck::default());
+
+        assert!(stats.consensus);
+        assert!(stats.highest.is_empty());
+        assert_eq!(stats.to_string(), "consensus on 5");
+    }
+
+    #[test]
+    fn test_stats_without_numbers() {
+        let stats = VoteStats::compute(
+            &votes(&[("A", "S"), ("B", "XL"), ("C", "not voted")]),
+            &Deck::t_shirt(),
+        );
+
+        assert_eq!(stats.mean, None);
+        assert_eq!(stats.spread, 3);
+        assert_eq!(stats.mode, vec![Vote::from("S"), Vote::from("XL")]);
+        assert_eq!(
+            stats.to_string(),
+            "min S, max XL, median S, mode S/XL, spread 3; discuss: B (highest) and A (lowest)"
+        );
+
+        let stats = VoteStats::compute(&votes(&[("A", "?")]), &Deck::default());
+        assert_eq!(stats.to_string(), "1 unknown");
+    }
+}
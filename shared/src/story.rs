@@ -0,0 +1,101 @@
+use super::vote::Vote;
+use serde::{Deserialize, Serialize};
+
+const MAX_KEY_LEN: usize = 16;
+const MAX_TITLE_LEN: usize = 120;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Story {
+    pub key: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Vote>,
+}
+
+impl Story {
+    pub fn new(key: &str, title: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            title: title.to_string(),
+            link: None,
+            description: None,
+            estimate: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.key.is_empty()
+            || self.key.chars().count() > MAX_KEY_LEN
+            || self.key.contains(char::is_whitespace)
+        {
+            return Err(format!(
+                "A story key must be a single word of up to {} characters",
+                MAX_KEY_LEN
+            ));
+        }
+
+        if self.title.trim().is_empty() || self.title.chars().count() > MAX_TITLE_LEN {
+            return Err(format!(
+                "A story title must have between 1 and {} characters",
+                MAX_TITLE_LEN
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Story {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.key, self.title)?;
+
+        if let Some(estimate) = &self.estimate {
+            write!(f, " = {}", estimate)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_story_validate() {
+        assert_eq!(Story::new("PROJ-1", "Login page").validate(), Ok(()));
+        assert!(Story::new("", "Login page").validate().is_err());
+        assert!(Story::new("PROJ 1", "Login page").validate().is_err());
+        assert!(Story::new("PROJ-1", " ").validate().is_err());
+    }
+
+    #[test]
+    fn test_story_display() {
+        let mut story = Story::new("PROJ-1", "Login page");
+        assert_eq!(story.to_string(), "PROJ-1 Login page");
+
+        story.estimate = Some(Vote::from(5));
+        assert_eq!(story.to_string(), "PROJ-1 Login page = 5");
+    }
+
+    #[test]
+    fn test_story_json() {
+        let story: Story =
+            serde_json::from_value(json!({"key": "PROJ-1", "title": "Login page"})).unwrap();
+        assert_eq!(story, Story::new("PROJ-1", "Login page"));
+
+        let story = Story {
+            estimate: Some(Vote::Unknown),
+            ..story
+        };
+        assert_eq!(
+            serde_json::to_value(&story).unwrap(),
+            json!({"key": "PROJ-1", "title": "Login page", "estimate": "?"})
+        );
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize, Serializer};
 pub enum Vote {
     Null,
     Unknown,
-    Option(usize),
+    Option(String),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -56,12 +56,10 @@ impl Serialize for VoteStatus {
 
 impl From<&str> for Vote {
     fn from(value: &str) -> Self {
-        match value {
+        match value.trim() {
             "?" => Vote::Unknown,
-            text => match text.trim().parse() {
-                Ok(value) => Vote::new(value),
-                Err(_) => Vote::Null,
-            },
+            "" | "not voted" => Vote::Null,
+            label => Vote::Option(label.to_string()),
         }
     }
 }
@@ -74,7 +72,7 @@ impl From<String> for Vote {
 
 impl From<usize> for Vote {
     fn from(value: usize) -> Self {
-        Vote::new(value)
+        Vote::Option(value.to_string())
     }
 }
 
@@ -113,14 +111,6 @@ impl std::fmt::Display for VoteStatus {
 }
 
 impl Vote {
-    pub fn new(value: usize) -> Self {
-        match value {
-            // Fibonacci sequence
-            1 | 2 | 3 | 5 | 8 | 13 => Vote::Option(value),
-            _ => Vote::Null,
-        }
-    }
-
     pub fn status(&self) -> VoteStatus {
         match self {
             Vote::Null => VoteStatus::NotVoted,
@@ -141,52 +131,45 @@ mod tests {
     #[test]
     fn test_vote_from_str() {
         assert_eq!(Vote::from("?"), Vote::Unknown);
-        assert_eq!(Vote::from("1"), Vote::Option(1));
-        assert_eq!(Vote::from("2"), Vote::Option(2));
-        assert_eq!(Vote::from("3"), Vote::Option(3));
-        assert_eq!(Vote::from("5"), Vote::Option(5));
-        assert_eq!(Vote::from("8"), Vote::Option(8));
-        assert_eq!(Vote::from("13"), Vote::Option(13));
-        assert_eq!(Vote::from("21"), Vote::Null);
-        assert_eq!(Vote::from("invalid"), Vote::Null);
+        assert_eq!(Vote::from("1"), Vote::Option("1".into()));
+        assert_eq!(Vote::from(" 13 "), Vote::Option("13".into()));
+        assert_eq!(Vote::from("XL"), Vote::Option("XL".into()));
+        assert_eq!(Vote::from("½"), Vote::Option("½".into()));
+        assert_eq!(Vote::from(""), Vote::Null);
+        assert_eq!(Vote::from("not voted"), Vote::Null);
     }
 
     #[test]
-    fn test_vote_new() {
-        assert_eq!(Vote::new(1), Vote::Option(1));
-        assert_eq!(Vote::new(2), Vote::Option(2));
-        assert_eq!(Vote::new(3), Vote::Option(3));
-        assert_eq!(Vote::new(5), Vote::Option(5));
-        assert_eq!(Vote::new(8), Vote::Option(8));
-        assert_eq!(Vote::new(13), Vote::Option(13));
-        assert_eq!(Vote::new(21), Vote::Null);
+    fn test_vote_from_usize() {
+        assert_eq!(Vote::from(1), Vote::Option("1".into()));
+        assert_eq!(Vote::from(21), Vote::Option("21".into()));
     }
 
     #[test]
     fn test_vote_status() {
         assert_eq!(Vote::Null.status().to_string(), "not voted");
         assert_eq!(Vote::Unknown.status().to_string(), "voted");
-        assert_eq!(Vote::Option(1).status().to_string(), "voted");
+        assert_eq!(Vote::from(1).status().to_string(), "voted");
     }
 
     #[test]
     fn test_vote_display() {
         assert_eq!(format!("{}", Vote::Null), "not voted");
         assert_eq!(format!("{}", Vote::Unknown), "?");
-        assert_eq!(format!("{}", Vote::Option(1)), "1");
-        assert_eq!(format!("{}", Vote::Option(8)), "8");
+        assert_eq!(format!("{}", Vote::from(1)), "1");
+        assert_eq!(format!("{}", Vote::from("XL")), "XL");
     }
 
     #[test]
     fn test_vote_is_valid() {
         assert!(!Vote::Null.is_valid_vote());
         assert!(Vote::Unknown.is_valid_vote());
-        assert!(Vote::Option(1).is_valid_vote());
+        assert!(Vote::from(1).is_valid_vote());
     }
 
     #[test]
     fn test_from_json() {
-        let vote = Vote::Option(1);
+        let vote = Vote::from(1);
         let json = json!("1");
         let vote_deserialized: Vote = serde_json::from_value(json).unwrap();
         assert_eq!(vote, vote_deserialized);
@@ -197,11 +180,16 @@ mod tests {
         assert_eq!(vote, vote_deserialized);
 
         let vote = Vote::Null;
-        let json = json!("0");
+        let json = json!("not voted");
+        let vote_deserialized: Vote = serde_json::from_value(json).unwrap();
+        assert_eq!(vote, vote_deserialized);
+
+        let vote = Vote::from("☕");
+        let json = json!("☕");
         let vote_deserialized: Vote = serde_json::from_value(json).unwrap();
         assert_eq!(vote, vote_deserialized);
 
-        let vote = Vote::new(1);
+        let vote = Vote::from(1);
         let json = json!(vote.status());
         let vote_deserialized: VoteStatus = serde_json::from_value(json).unwrap();
         assert_eq!(vote.status(), vote_deserialized);
@@ -0,0 +1,300 @@
+use omnipoker_client::Event;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use shared::{
+    Deck, InboundMessage, OutboundMessage, Participant, UserStatus, Vote, VoteStats, VoteStatus,
+};
+use std::collections::VecDeque;
+
+/// Lines kept in the message log.
+const LOG_SIZE: usize = 100;
+
+/// Keys picking a card, by its position in the deck.
+pub const CARD_KEYS: [char; 10] = ['1', '2', '3', '4', '5', '6', '7', '8', '9', '0'];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Round {
+    Voting(Vec<(String, VoteStatus)>),
+    Revealed(Vec<(String, Vote)>),
+}
+
+/// Where a user stands in the round.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mark {
+    Waiting,
+    Voted,
+    Card(Vote),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Connection {
+    Connected,
+    Reconnecting(u32),
+    Closed,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Send(InboundMessage),
+    Quit,
+}
+
+#[derive(Debug)]
+pub struct App {
+    pub nickname: String,
+    pub room: String,
+    pub connection: Connection,
+    pub users: Vec<Participant>,
+    pub deck: Deck,
+    pub round: Round,
+    pub stats: Option<VoteStats>,
+    pub story: Option<String>,
+    pub timer: Option<u64>,
+    pub your_vote: Vote,
+    pub your_status: UserStatus,
+    pub log: VecDeque<String>,
+}
+
+impl App {
+    pub fn new(nickname: &str, room: &str) -> Self {
+        Self {
+            nickname: nickname.to_string(),
+            room: room.to_string(),
+            connection: Connection::Connected,
+            users: Vec::new(),
+            deck: Deck::default(),
+            round: Round::Voting(Vec::new()),
+            stats: None,
+            story: None,
+            timer: None,
+            your_vote: Vote::Null,
+            your_status: UserStatus::Active,
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn push_log(&mut self, line: String) {
+        if self.log.len() == LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::Message(message) => self.apply_message(message),
+            Event::Reconnecting { attempt } => {
+                if attempt == 1 {
+                    self.push_log("Connection lost, reconnecting".to_string());
+                }
+                self.connection = Connection::Reconnecting(attempt);
+            }
+            Event::Reconnected => {
+                self.push_log("Reconnected".to_string());
+                self.connection = Connection::Connected;
+            }
+            Event::Closed(reason) => {
+                self.push_log(match reason {
+                    Some(reason) if !reason.is_empty() => format!("Disconnected: {}", reason),
+                    _ => "Disconnected".to_string(),
+                });
+                self.connection = Connection::Closed;
+            }
+        }
+    }
+
+    fn apply_message(&mut self, message: OutboundMessage) {
+        match message {
+            OutboundMessage::UserList(users) => self.users = users,
+            OutboundMessage::Deck(deck) => self.deck = deck,
+            OutboundMessage::VotesStatus { votes, story } => {
+                self.round = Round::Voting(votes);
+                self.stats = None;
+                self.story = story;
+            }
+            OutboundMessage::Stats(stats) => self.stats = Some(stats),
+            OutboundMessage::YourVote(vote) => self.your_vote = vote,
+            OutboundMessage::YourStatus(status) => self.your_status = status,
+            OutboundMessage::Stories { current, .. } => self.story = current,
+            OutboundMessage::TimerStarted { seconds, .. } | OutboundMessage::TimerTick(seconds) => {
+                self.timer = Some(seconds)
+            }
+            OutboundMessage::TimerStopped => self.timer = None,
+            OutboundMessage::ResumeToken(_) | OutboundMessage::Welcome { .. } => {}
+            message => {
+                if let OutboundMessage::VotesResult { votes, story } = &message {
+                    self.round = Round::Revealed(votes.clone());
+                    self.story = story.clone();
+                }
+                if message == OutboundMessage::TimerExpired {
+                    self.timer = None;
+                }
+                self.push_log(message.to_string());
+            }
+        }
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Action::Quit);
+        }
+
+        let message = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Char(c) if CARD_KEYS.contains(&c) => {
+                let position = CARD_KEYS.iter().position(|key| *key == c)?;
+                let card = self.deck.cards.get(position)?.clone();
+                InboundMessage::Vote { value: card }
+            }
+            KeyCode::Char('x') | KeyCode::Backspace => InboundMessage::Vote { value: Vote::Null },
+            KeyCode::Char('a') => match self.your_status {
+                UserStatus::Active => InboundMessage::SetStatus(UserStatus::Away),
+                UserStatus::Away => InboundMessage::SetStatus(UserStatus::Active),
+                UserStatus::Observer => return None,
+            },
+            KeyCode::Char('r') => InboundMessage::Reveal,
+            KeyCode::Char('n') => InboundMessage::NextRound,
+            _ => return None,
+        };
+
+        Some(Action::Send(message))
+    }
+
+    /// `None` when the user isn't part of the round.
+    pub fn mark_of(&self, nickname: &str) -> Option<Mark> {
+        match &self.round {
+            Round::Voting(votes) => {
+                votes
+                    .iter()
+                    .find(|(user, _)| user == nickname)
+                    .map(|(_, status)| match status {
+                        VoteStatus::Voted => Mark::Voted,
+                        VoteStatus::NotVoted => Mark::Waiting,
+                    })
+            }
+            Round::Revealed(votes) => votes
+                .iter()
+                .find(|(user, _)| user == nickname)
+                .map(|(_, vote)| Mark::Card(vote.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        app.on_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_apply_round() {
+        let mut app = App::new("Alice", "default");
+
+        app.apply(Event::Message(OutboundMessage::VotesStatus {
+            votes: vec![
+                ("Alice".to_string(), VoteStatus::Voted),
+                ("Bob".to_string(), VoteStatus::NotVoted),
+            ],
+            story: None,
+        }));
+        assert_eq!(app.mark_of("Alice"), Some(Mark::Voted));
+        assert_eq!(app.mark_of("Bob"), Some(Mark::Waiting));
+        assert_eq!(app.mark_of("Carol"), None);
+        assert!(app.log.is_empty());
+
+        app.apply(Event::Message(OutboundMessage::VotesResult {
+            votes: vec![
+                ("Alice".to_string(), Vote::from("3")),
+                ("Bob".to_string(), Vote::from("5")),
+            ],
+            story: Some("PROJ-1".to_string()),
+        }));
+        assert_eq!(app.mark_of("Bob"), Some(Mark::Card(Vote::from("5"))));
+        assert_eq!(app.story, Some("PROJ-1".to_string()));
+        // revealed rounds stay in the log
+        assert_eq!(app.log.len(), 1);
+
+        app.apply(Event::Message(OutboundMessage::VotesStatus {
+            votes: Vec::new(),
+            story: None,
+        }));
+        assert_eq!(app.round, Round::Voting(Vec::new()));
+        assert_eq!(app.stats, None);
+    }
+
+    #[test]
+    fn test_apply_connection() {
+        let mut app = App::new("Alice", "default");
+
+        app.apply(Event::Reconnecting { attempt: 1 });
+        app.apply(Event::Reconnecting { attempt: 2 });
+        assert_eq!(app.connection, Connection::Reconnecting(2));
+        app.apply(Event::Reconnected);
+        assert_eq!(app.connection, Connection::Connected);
+        app.apply(Event::Closed(Some("Kicked".to_string())));
+        assert_eq!(app.connection, Connection::Closed);
+
+        assert_eq!(
+            Vec::from(app.log),
+            vec![
+                "Connection lost, reconnecting",
+                "Reconnected",
+                "Disconnected: Kicked"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_size() {
+        let mut app = App::new("Alice", "default");
+        for i in 0..LOG_SIZE + 5 {
+            app.push_log(i.to_string());
+        }
+
+        assert_eq!(app.log.len(), LOG_SIZE);
+        assert_eq!(app.log.front(), Some(&"5".to_string()));
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut app = App::new("Alice", "default");
+
+        // the default deck starts with `?`
+        assert_eq!(
+            press(&mut app, KeyCode::Char('1')),
+            Some(Action::Send(InboundMessage::Vote {
+                value: Vote::Unknown
+            }))
+        );
+        assert_eq!(
+            press(&mut app, KeyCode::Char('5')),
+            Some(Action::Send(InboundMessage::Vote {
+                value: Vote::from("5")
+            }))
+        );
+        assert_eq!(press(&mut app, KeyCode::Char('9')), None);
+        assert_eq!(
+            press(&mut app, KeyCode::Char('x')),
+            Some(Action::Send(InboundMessage::Vote { value: Vote::Null }))
+        );
+
+        assert_eq!(
+            press(&mut app, KeyCode::Char('a')),
+            Some(Action::Send(InboundMessage::SetStatus(UserStatus::Away)))
+        );
+        app.apply(Event::Message(OutboundMessage::YourStatus(
+            UserStatus::Away,
+        )));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('a')),
+            Some(Action::Send(InboundMessage::SetStatus(UserStatus::Active)))
+        );
+
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(
+            app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use app::{Action, App};
+use clap::Parser;
+use omnipoker_client::Client;
+use ratatui::{
+    crossterm::event::{self, Event as TermEvent, KeyEventKind},
+    DefaultTerminal,
+};
+use shared::InboundMessage;
+use std::{error::Error, thread};
+use tokio::sync::mpsc;
+
+mod app;
+mod ui;
+
+#[derive(clap::Parser)]
+#[command(version, about = "Play planning poker from the terminal.", long_about = None)]
+struct Cli {
+    #[arg(
+        default_value = "ws://127.0.0.1:8080/ws",
+        help = "WebSocket address of the server."
+    )]
+    url: String,
+    #[arg(short, long, help = "Your nickname in the room.")]
+    nickname: String,
+    #[arg(short, long, help = "Room to join, instead of the default one.")]
+    room: Option<String>,
+    #[arg(long, help = "Password of a protected room.")]
+    password: Option<String>,
+    #[arg(long, help = "Follow the session without voting.")]
+    observer: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let client = Client::connect(&cli.url).await?;
+    client.send(InboundMessage::Connect {
+        nickname: cli.nickname.clone(),
+        room: cli.room.clone(),
+        observer: cli.observer,
+        password: cli.password,
+        invite: None,
+    })?;
+
+    let app = App::new(&cli.nickname, cli.room.as_deref().unwrap_or("default"));
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, client, app).await;
+    ratatui::restore();
+
+    result
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    mut client: Client,
+    mut app: App,
+) -> Result<(), Box<dyn Error>> {
+    // crossterm only reads the terminal in a blocking way
+    let (terminal_tx, mut terminal_rx) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if terminal_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    // the screen stays up after the session ends, until the user quits
+    let mut ended = false;
+
+    loop {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+
+        tokio::select! {
+            event = terminal_rx.recv() => match event {
+                Some(TermEvent::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match app.on_key(key) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(Action::Send(message)) => {
+                            if let Err(err) = client.send(message) {
+                                app.push_log(err.to_string());
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                Some(_) => {}
+                None => return Ok(()),
+            },
+            event = client.next_event(), if !ended => match event {
+                Some(event) => app.apply(event),
+                None => ended = true,
+            },
+        }
+    }
+}
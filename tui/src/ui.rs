@@ -0,0 +1,219 @@
+use crate::app::{App, Connection, Mark, Round, CARD_KEYS};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+use shared::{UserStatus, VoteStatus};
+
+const HELP: &str = "1-9,0 vote · x retract · a away · r reveal · n next round · q quit";
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, cards, log, help] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(6),
+        Constraint::Length(4),
+        Constraint::Length(8),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [users, round] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+
+    draw_header(frame, header, app);
+    draw_users(frame, users, app);
+    draw_round(frame, round, app);
+    draw_cards(frame, cards, app);
+    draw_log(frame, log, app);
+    frame.render_widget(Paragraph::new(HELP).dark_gray(), help);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, app: &App) {
+    let connection = match app.connection {
+        Connection::Connected => "connected".green(),
+        Connection::Reconnecting(attempt) => format!("reconnecting (attempt {})", attempt).yellow(),
+        Connection::Closed => "disconnected".red(),
+    };
+    let status = match app.your_status {
+        UserStatus::Active => "active",
+        UserStatus::Away => "away",
+        UserStatus::Observer => "observer",
+    };
+
+    let mut spans = vec![
+        "omnipoker".bold(),
+        format!(" · {} · {} ({}) · ", app.room, app.nickname, status).into(),
+        connection,
+    ];
+    if let Some(story) = &app.story {
+        spans.push(format!(" · {}", story).into());
+    }
+    if let Some(seconds) = app.timer {
+        spans.push(format!(" · {}:{:02}", seconds / 60, seconds % 60).cyan());
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_users(frame: &mut Frame, area: Rect, app: &App) {
+    let items = app
+        .users
+        .iter()
+        .map(|user| {
+            let vote = match app.mark_of(&user.nickname) {
+                Some(Mark::Voted) => "✔".green(),
+                Some(Mark::Waiting) => "…".yellow(),
+                Some(Mark::Card(vote)) => vote.to_string().bold(),
+                None => "-".dark_gray(),
+            };
+            let mut name = Span::raw(user.to_string());
+            if user.nickname == app.nickname {
+                name = name.bold();
+            }
+
+            ListItem::new(Line::from(vec![vote, " ".into(), name]))
+        })
+        .collect::<Vec<_>>();
+
+    let title = format!(" Users ({}) ", app.users.len());
+    frame.render_widget(List::new(items).block(Block::bordered().title(title)), area);
+}
+
+fn draw_round(frame: &mut Frame, area: Rect, app: &App) {
+    let lines = match &app.round {
+        Round::Voting(votes) => {
+            let voted = votes
+                .iter()
+                .filter(|(_, status)| *status == VoteStatus::Voted)
+                .count();
+            vec![Line::from(format!("{} of {} voted", voted, votes.len()))]
+        }
+        Round::Revealed(votes) => {
+            let mut lines = votes
+                .iter()
+                .map(|(nickname, vote)| {
+                    Line::from(vec![
+                        format!("{:>4} ", vote.to_string()).bold(),
+                        nickname.clone().into(),
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(stats) = &app.stats {
+                lines.push(Line::default());
+                let summary = Span::raw(stats.to_string());
+                lines.push(Line::from(match stats.consensus {
+                    true => summary.green(),
+                    false => summary,
+                }));
+            }
+            lines
+        }
+    };
+
+    let title = match app.round {
+        Round::Voting(_) => " Voting ",
+        Round::Revealed(_) => " Revealed ",
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+fn draw_cards(frame: &mut Frame, area: Rect, app: &App) {
+    let mut cards = Vec::new();
+    let mut keys = Vec::new();
+
+    for (card, key) in app.deck.cards.iter().zip(CARD_KEYS) {
+        let label = format!(" {:^4} ", card.to_string());
+        let width = label.chars().count();
+
+        let style = match *card == app.your_vote {
+            true => Style::default()
+                .fg(Color::Black)
+                .bg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            false => Style::default().add_modifier(Modifier::REVERSED),
+        };
+        cards.push(Span::styled(label, style));
+        cards.push(" ".into());
+        keys.push(format!("{:^width$} ", key, width = width).dark_gray());
+    }
+
+    let title = format!(" Cards: {} ", app.deck.name);
+    frame.render_widget(
+        Paragraph::new(vec![Line::from(cards), Line::from(keys)])
+            .block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+fn draw_log(frame: &mut Frame, area: Rect, app: &App) {
+    // the latest lines that fit between the borders
+    let height = area.height.saturating_sub(2) as usize;
+    let lines = app
+        .log
+        .iter()
+        .skip(app.log.len().saturating_sub(height))
+        .map(|line| Line::from(line.as_str()))
+        .collect::<Vec<_>>();
+
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Messages ")),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use omnipoker_client::Event;
+    use ratatui::{backend::TestBackend, Terminal};
+    use shared::{OutboundMessage, Participant, Vote};
+
+    #[test]
+    fn test_draw() {
+        let mut app = App::new("Alice", "team-a");
+        app.apply(Event::Message(OutboundMessage::UserList(vec![
+            Participant {
+                nickname: "Alice".to_string(),
+                facilitator: true,
+                observer: false,
+            },
+            Participant {
+                nickname: "Bob".to_string(),
+                facilitator: false,
+                observer: false,
+            },
+        ])));
+        app.apply(Event::Message(OutboundMessage::VotesResult {
+            votes: vec![
+                ("Alice".to_string(), Vote::from("3")),
+                ("Bob".to_string(), Vote::from("13")),
+            ],
+            story: None,
+        }));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+
+        let screen = terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(80)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(screen.contains("team-a · Alice (active)"));
+        assert!(screen.contains("Alice (facilitator)"));
+        assert!(screen.contains("Revealed"));
+        assert!(screen.contains("  13 Bob"));
+        assert!(screen.contains("Cards: fibonacci"));
+    }
+}